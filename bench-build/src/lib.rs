@@ -0,0 +1,133 @@
+//! Build script helper of test-hash and test-compress, describing the build for the runs they
+//! record to the history database. It has no dependencies so the build scripts stay quick to build.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Makes the build a run is recorded with available to the tool through `env!`: the target
+/// triple as `TARGET`, the git commit as `GIT_COMMIT`, the rustc version as `RUSTC_VERSION`, and
+/// the names and versions of the crates it depends on in `crates.txt` in `OUT_DIR`, to call from
+/// the build script of the tool
+pub fn emit() {
+    // the target triple, also shown in the run metadata of the structured output formats
+    println!(
+        "cargo:rustc-env=TARGET={}",
+        std::env::var("TARGET").unwrap()
+    );
+
+    // the commit, compiler and crate versions of the build, recorded with each run in the history
+    // database
+    let manifest_dir = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap());
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let git_dir = output(Command::new("git").args(["rev-parse", "--absolute-git-dir"]));
+    if let Some(git_dir) = &git_dir {
+        println!("cargo:rerun-if-changed={}/HEAD", git_dir);
+        println!("cargo:rerun-if-changed={}/logs/HEAD", git_dir);
+    }
+    let commit = git_dir
+        .and_then(|_| output(Command::new("git").args(["rev-parse", "HEAD"])))
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=GIT_COMMIT={}", commit);
+
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let rustc_version =
+        output(Command::new(rustc).arg("--version")).unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=RUSTC_VERSION={}", rustc_version);
+
+    let crates = match manifest_dir
+        .ancestors()
+        .map(|dir| dir.join("Cargo.lock"))
+        .find(|path| path.exists())
+    {
+        Some(lock) => {
+            println!("cargo:rerun-if-changed={}", lock.display());
+            dependencies(&lock, &std::env::var("CARGO_PKG_NAME").unwrap())
+        }
+        None => BTreeSet::new(),
+    };
+    let crates = crates
+        .iter()
+        .map(|(name, version)| format!("{} {}\n", name, version))
+        .collect::<String>();
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").unwrap());
+    std::fs::write(out_dir.join("crates.txt"), crates).unwrap();
+}
+
+/// Trimmed stdout of a command that succeeded
+fn output(command: &mut Command) -> Option<String> {
+    let output = command.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8(output.stdout).ok()?.trim().to_string())
+}
+
+/// Value of a `key = "value"` line
+fn value(line: &str) -> &str {
+    line.split_once('=').unwrap().1.trim().trim_matches('"')
+}
+
+struct Package {
+    name: String,
+    version: String,
+    /// `name` or `name version` when the lock file has several versions of the crate
+    dependencies: Vec<String>,
+}
+
+/// Names and versions of the crates in the lock file that the package depends on, directly or
+/// through other crates
+fn dependencies(lock: &Path, package: &str) -> BTreeSet<(String, String)> {
+    let contents = std::fs::read_to_string(lock).unwrap_or_default();
+
+    let mut packages = Vec::<Package>::new();
+    let mut in_dependencies = false;
+    for line in contents.lines() {
+        let line = line.trim();
+        if line == "[[package]]" {
+            packages.push(Package {
+                name: String::new(),
+                version: String::new(),
+                dependencies: vec![],
+            });
+        } else if let Some(package) = packages.last_mut() {
+            if in_dependencies {
+                if line == "]" {
+                    in_dependencies = false;
+                } else {
+                    // older lock files also name the source after the version
+                    let dependency = line.trim_end_matches(',').trim_matches('"');
+                    let dependency = dependency.split(' ').take(2).collect::<Vec<_>>();
+                    package.dependencies.push(dependency.join(" "));
+                }
+            } else if line.starts_with("name = ") {
+                package.name = value(line).to_string();
+            } else if line.starts_with("version = ") {
+                package.version = value(line).to_string();
+            } else if line == "dependencies = [" {
+                in_dependencies = true;
+            }
+        }
+    }
+
+    let mut crates = BTreeSet::new();
+    let mut stack = vec![package.to_string()];
+    while let Some(dependency) = stack.pop() {
+        let mut parts = dependency.split(' ');
+        let (name, version) = (parts.next().unwrap(), parts.next());
+        let found = packages
+            .iter()
+            .find(|p| p.name == name && (version.is_none() || version == Some(p.version.as_str())));
+        if let Some(found) = found {
+            if crates.insert((found.name.clone(), found.version.clone())) {
+                stack.extend(found.dependencies.iter().cloned());
+            }
+        }
+    }
+    crates.remove(&(
+        package.to_string(),
+        std::env::var("CARGO_PKG_VERSION").unwrap(),
+    ));
+    crates
+}
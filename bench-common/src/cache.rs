@@ -0,0 +1,99 @@
+//! Warm and cold cache measurement modes, cold runs sweep a buffer larger than the last-level cache
+//! before each timed run
+
+use std::{fmt, str::FromStr};
+
+/// Assumed last-level cache size where it can't be read from the OS
+const DEFAULT_LAST_LEVEL_CACHE: usize = 32 * 1024 * 1024;
+
+const CACHE_LINE: usize = 64;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum CacheMode {
+    /// Input already in cache from creating it or from the previous run
+    Warm,
+    /// Caches evicted before each timed run, so the input is read from memory
+    Cold,
+}
+
+impl FromStr for CacheMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "warm" => Ok(CacheMode::Warm),
+            "cold" => Ok(CacheMode::Cold),
+            _ => Err(format!("unknown cache mode {}, expected warm or cold", s)),
+        }
+    }
+}
+
+impl fmt::Display for CacheMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CacheMode::Warm => "warm",
+            CacheMode::Cold => "cold",
+        })
+    }
+}
+
+/// Evicts the caches before cold runs by writing to every cache line of a buffer twice the size of
+/// the last-level cache. Other cores' private caches are only evicted if the last-level cache is
+/// inclusive.
+pub struct Evictor {
+    buffer: Vec<u8>,
+}
+
+impl Evictor {
+    /// Only allocates the eviction buffer if one of the modes is cold
+    pub fn new(modes: &[CacheMode]) -> Self {
+        let size = if modes.contains(&CacheMode::Cold) {
+            2 * last_level_cache_size().unwrap_or(DEFAULT_LAST_LEVEL_CACHE)
+        } else {
+            0
+        };
+        Self {
+            buffer: vec![0u8; size],
+        }
+    }
+
+    /// Gets the caches into the state of the mode before a timed run
+    pub fn prepare(&mut self, mode: CacheMode) {
+        if mode == CacheMode::Cold {
+            for line in self.buffer.chunks_mut(CACHE_LINE) {
+                line[0] = line[0].wrapping_add(1);
+            }
+            std::hint::black_box(&self.buffer);
+        }
+    }
+}
+
+/// Size of the highest level data or unified cache of the first CPU, from sysfs
+#[cfg(target_os = "linux")]
+fn last_level_cache_size() -> Option<usize> {
+    let mut caches = vec![];
+    for entry in std::fs::read_dir("/sys/devices/system/cpu/cpu0/cache").ok()? {
+        let path = entry.ok()?.path();
+        let read = |name| std::fs::read_to_string(path.join(name)).ok();
+        if read("type")?.trim() == "Instruction" {
+            continue;
+        }
+        let level = read("level")?.trim().parse::<u32>().ok()?;
+        let size = read("size")?;
+        let size = size.trim();
+        let size = match size.strip_suffix('K') {
+            Some(kib) => kib.parse::<usize>().ok()? * 1024,
+            None => match size.strip_suffix('M') {
+                Some(mib) => mib.parse::<usize>().ok()? * 1024 * 1024,
+                None => size.parse().ok()?,
+            },
+        };
+        caches.push((level, size));
+    }
+    caches.into_iter().max().map(|(_, size)| size)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn last_level_cache_size() -> Option<usize> {
+    None
+}
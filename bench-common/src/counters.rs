@@ -0,0 +1,188 @@
+//! Hardware performance counters through Linux `perf_event_open`, to report cycles and
+//! instructions per byte that are comparable across machines with different clock speeds
+
+/// Counter values over one measurement, scaled up if the kernel multiplexed the counters
+#[derive(Copy, Clone, Debug)]
+pub struct Counts {
+    pub cycles: u64,
+    pub instructions: u64,
+    pub cache_misses: u64,
+    pub branch_misses: u64,
+}
+
+/// Counts of a measurement relative to the bytes it processed
+#[derive(serde::Serialize)]
+pub struct CounterResult {
+    pub cycles_per_byte: f64,
+    pub instructions_per_byte: f64,
+    pub ipc: f64,
+    pub cycles: u64,
+    pub instructions: u64,
+    pub cache_misses: u64,
+    pub branch_misses: u64,
+}
+
+impl Counts {
+    pub fn per_byte(self, bytes: usize) -> CounterResult {
+        CounterResult {
+            cycles_per_byte: self.cycles as f64 / bytes as f64,
+            instructions_per_byte: self.instructions as f64 / bytes as f64,
+            ipc: self.instructions as f64 / self.cycles as f64,
+            cycles: self.cycles,
+            instructions: self.instructions,
+            cache_misses: self.cache_misses,
+            branch_misses: self.branch_misses,
+        }
+    }
+}
+
+/// Group of cycle, instruction, cache miss and branch miss counters for the calling thread, in
+/// user space only. Work done on other threads, such as the multithreaded runs, isn't counted.
+#[cfg(target_os = "linux")]
+pub struct Counters {
+    /// The group leader, counting cycles, followed by the other counters
+    fds: Vec<std::os::raw::c_int>,
+}
+
+#[cfg(target_os = "linux")]
+impl Counters {
+    pub fn new() -> Result<Self, String> {
+        use perf_event_open_sys::bindings::*;
+
+        let mut counters = Self { fds: vec![] };
+        for &config in &[
+            perf_hw_id_PERF_COUNT_HW_CPU_CYCLES,
+            perf_hw_id_PERF_COUNT_HW_INSTRUCTIONS,
+            perf_hw_id_PERF_COUNT_HW_CACHE_MISSES,
+            perf_hw_id_PERF_COUNT_HW_BRANCH_MISSES,
+        ] {
+            let mut attr = perf_event_attr {
+                type_: perf_type_id_PERF_TYPE_HARDWARE,
+                size: std::mem::size_of::<perf_event_attr>() as u32,
+                config: u64::from(config),
+                read_format: u64::from(
+                    perf_event_read_format_PERF_FORMAT_GROUP
+                        | perf_event_read_format_PERF_FORMAT_TOTAL_TIME_ENABLED
+                        | perf_event_read_format_PERF_FORMAT_TOTAL_TIME_RUNNING,
+                ),
+                ..Default::default()
+            };
+            // the members follow the leader, which starts disabled until a measurement
+            attr.set_disabled(counters.fds.is_empty() as u64);
+            attr.set_exclude_kernel(1);
+            attr.set_exclude_hv(1);
+
+            let group_fd = counters.fds.first().copied().unwrap_or(-1);
+            let fd = unsafe {
+                perf_event_open_sys::perf_event_open(
+                    &mut attr,
+                    0,
+                    -1,
+                    group_fd,
+                    PERF_FLAG_FD_CLOEXEC.into(),
+                )
+            };
+            if fd < 0 {
+                return Err(format!(
+                    "perf_event_open failed: {}, hardware counters need a PMU and \
+                     /proc/sys/kernel/perf_event_paranoid at 2 or lower",
+                    std::io::Error::last_os_error()
+                ));
+            }
+            counters.fds.push(fd);
+        }
+        Ok(counters)
+    }
+
+    /// Runs `f` with the counters enabled
+    pub fn measure<T>(&mut self, f: impl FnOnce() -> T) -> (T, Counts) {
+        use perf_event_open_sys::{bindings::perf_event_ioc_flags_PERF_IOC_FLAG_GROUP, ioctls};
+
+        let leader = self.fds[0];
+        unsafe {
+            ioctls::RESET(leader, perf_event_ioc_flags_PERF_IOC_FLAG_GROUP);
+            ioctls::ENABLE(leader, perf_event_ioc_flags_PERF_IOC_FLAG_GROUP);
+        }
+        let result = f();
+        unsafe { ioctls::DISABLE(leader, perf_event_ioc_flags_PERF_IOC_FLAG_GROUP) };
+
+        // number of counters, time enabled, time running, then the value of each counter
+        let mut values = [0u64; 7];
+        let read = unsafe {
+            libc::read(
+                leader,
+                values.as_mut_ptr() as *mut libc::c_void,
+                std::mem::size_of_val(&values),
+            )
+        };
+        assert_eq!(
+            read as usize,
+            std::mem::size_of_val(&values),
+            "failed to read the performance counters"
+        );
+
+        let (enabled, running) = (values[1], values[2]);
+        let scale = |v: u64| {
+            if running == 0 || running == enabled {
+                v
+            } else {
+                (v as f64 * enabled as f64 / running as f64) as u64
+            }
+        };
+        let counts = Counts {
+            cycles: scale(values[3]),
+            instructions: scale(values[4]),
+            cache_misses: scale(values[5]),
+            branch_misses: scale(values[6]),
+        };
+        (result, counts)
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for Counters {
+    fn drop(&mut self) {
+        for &fd in &self.fds {
+            unsafe { libc::close(fd) };
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub enum Counters {}
+
+#[cfg(not(target_os = "linux"))]
+impl Counters {
+    pub fn new() -> Result<Self, String> {
+        Err("hardware counters are only supported on Linux".to_string())
+    }
+
+    pub fn measure<T>(&mut self, _f: impl FnOnce() -> T) -> (T, Counts) {
+        match *self {}
+    }
+}
+
+/// Runs `f`, with the counters enabled if there are any
+pub fn measure<T>(counters: &mut Option<Counters>, f: impl FnOnce() -> T) -> (T, Option<Counts>) {
+    match counters {
+        Some(counters) => {
+            let (result, counts) = counters.measure(f);
+            (result, Some(counts))
+        }
+        None => (f(), None),
+    }
+}
+
+/// Opens the counters if asked for, exiting with the error if they can't be
+pub fn open(enabled: bool) -> Option<Counters> {
+    if !enabled {
+        return None;
+    }
+    match Counters::new() {
+        Ok(counters) => Some(counters),
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,556 @@
+//! History of the runs in a SQLite database, with the build and machine they ran on, and trends
+//! of each result over the recorded runs
+
+use std::path::Path;
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::collections::{BTreeMap, BTreeSet};
+
+#[cfg(not(target_arch = "wasm32"))]
+use rusqlite::{params, Connection};
+
+/// Tables of the database, shared by test-hash and test-compress so both can record to the same file
+#[cfg(not(target_arch = "wasm32"))]
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    timestamp INTEGER NOT NULL,
+    tool TEXT NOT NULL,
+    command TEXT NOT NULL,
+    arguments TEXT NOT NULL,
+    git_commit TEXT NOT NULL,
+    rustc TEXT NOT NULL,
+    cpu_model TEXT NOT NULL,
+    target TEXT NOT NULL,
+    features TEXT NOT NULL,
+    cpu_features TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS crates (
+    run INTEGER NOT NULL REFERENCES runs (id),
+    name TEXT NOT NULL,
+    version TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS results (
+    run INTEGER NOT NULL REFERENCES runs (id),
+    name TEXT NOT NULL,
+    cache TEXT NOT NULL,
+    metric TEXT NOT NULL,
+    value REAL NOT NULL
+);
+CREATE INDEX IF NOT EXISTS results_run ON results (run);
+CREATE INDEX IF NOT EXISTS crates_run ON crates (run);
+";
+
+/// The build a run is recorded with, which the build script of the tool makes available through
+/// `env!`, see bench-build
+pub struct Build {
+    /// Package name of the tool, so test-hash and test-compress can share a database
+    pub tool: &'static str,
+    pub git_commit: &'static str,
+    pub rustc: &'static str,
+    pub target: &'static str,
+    /// Names and versions of the crates the build depends on, one `name version` per line
+    pub crates: &'static str,
+    /// Cargo features the build was made with
+    pub features: Vec<&'static str>,
+}
+
+/// Model name of the CPU as the OS reports it
+#[cfg(not(target_arch = "wasm32"))]
+fn cpu_model() -> String {
+    #[cfg(target_os = "linux")]
+    let model = std::fs::read_to_string("/proc/cpuinfo")
+        .ok()
+        .and_then(|cpuinfo| {
+            cpuinfo
+                .lines()
+                .find(|line| line.starts_with("model name"))
+                .and_then(|line| line.split_once(':'))
+                .map(|(_, model)| model.trim().to_string())
+        });
+
+    #[cfg(target_os = "macos")]
+    let model = std::process::Command::new("sysctl")
+        .args(["-n", "machdep.cpu.brand_string"])
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|model| model.trim().to_string());
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    let model = None;
+
+    model.unwrap_or_else(|| std::env::consts::ARCH.to_string())
+}
+
+/// A run being recorded, written to the database once it's finished so interrupted runs leave no
+/// partial results
+#[cfg(not(target_arch = "wasm32"))]
+pub struct History {
+    connection: Connection,
+    build: Build,
+    command: &'static str,
+    results: Vec<(String, String, &'static str, f64)>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl History {
+    pub fn new(path: &Path, build: Build, command: &'static str) -> Result<Self, String> {
+        let error = |e: rusqlite::Error| format!("failed to open {}: {}", path.display(), e);
+        let connection = Connection::open(path).map_err(error)?;
+        connection.execute_batch(SCHEMA).map_err(error)?;
+        Ok(Self {
+            connection,
+            build,
+            command,
+            results: vec![],
+        })
+    }
+
+    /// Adds a result of the run, `name` identifies the benchmark within the tool
+    pub fn add(&mut self, name: String, cache: &str, metric: &'static str, value: f64) {
+        self.results.push((name, cache.to_string(), metric, value));
+    }
+
+    /// Appends the run and its results, exiting with the error if they can't be
+    pub fn write(mut self) {
+        let result = (|| -> rusqlite::Result<()> {
+            let build = &self.build;
+            let transaction = self.connection.transaction()?;
+            let timestamp = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            let arguments = std::env::args().skip(1).collect::<Vec<_>>().join(" ");
+            transaction.execute(
+                "INSERT INTO runs (timestamp, tool, command, arguments, git_commit, rustc,
+                 cpu_model, target, features, cpu_features)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    timestamp as i64,
+                    build.tool,
+                    self.command,
+                    arguments,
+                    build.git_commit,
+                    build.rustc,
+                    cpu_model(),
+                    build.target,
+                    build.features.join(" "),
+                    crate::simd::cpu_features().join(" "),
+                ],
+            )?;
+            let run = transaction.last_insert_rowid();
+
+            for (name, version) in build.crates.lines().filter_map(|line| line.split_once(' ')) {
+                transaction.execute(
+                    "INSERT INTO crates (run, name, version) VALUES (?1, ?2, ?3)",
+                    params![run, name, version],
+                )?;
+            }
+            for (name, cache, metric, value) in &self.results {
+                transaction.execute(
+                    "INSERT INTO results (run, name, cache, metric, value)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![run, name, cache, metric, value],
+                )?;
+            }
+            transaction.commit()
+        })();
+
+        if let Err(err) = result {
+            eprintln!("failed to record the run: {}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub enum History {}
+
+#[cfg(target_arch = "wasm32")]
+impl History {
+    pub fn new(_path: &Path, _build: Build, _command: &'static str) -> Result<Self, String> {
+        Err("the history database isn't supported on wasm32".to_string())
+    }
+
+    pub fn add(&mut self, _name: String, _cache: &str, _metric: &'static str, _value: f64) {
+        match *self {}
+    }
+
+    pub fn write(self) {
+        match self {}
+    }
+}
+
+/// Opens the history database if asked for, exiting with the error if it can't be
+pub fn open(path: Option<&Path>, build: Build, command: &'static str) -> Option<History> {
+    match History::new(path?, build, command) {
+        Ok(history) => Some(history),
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// How the trends of the recorded results are shown
+pub struct Trends<'a> {
+    /// Package name of the tool whose results are shown
+    pub tool: &'a str,
+    /// Only results with this in their name are shown
+    pub filter: Option<&'a str>,
+    /// Changes from the baseline larger than this fraction are flagged as step changes
+    pub threshold: f64,
+    /// Number of earlier runs whose median is the baseline a run is compared against
+    pub window: usize,
+}
+
+/// Parses the number of runs in the window, which needs at least one to have a baseline
+pub fn parse_window(window: &str) -> Result<usize, String> {
+    match window.parse() {
+        Ok(0) => Err("the window needs at least one run".to_string()),
+        Ok(window) => Ok(window),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// A recorded result, with the run it's from
+#[cfg(not(target_arch = "wasm32"))]
+struct Row {
+    name: String,
+    cache: String,
+    metric: String,
+    cpu_model: String,
+    target: String,
+    run: i64,
+    date: String,
+    git_commit: String,
+    rustc: String,
+    features: String,
+    value: f64,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Row {
+    /// Results are only comparable to earlier ones of the same benchmark on the same machine
+    fn series(&self) -> (&str, &str, &str, &str, &str) {
+        (
+            &self.name,
+            &self.cache,
+            &self.metric,
+            &self.cpu_model,
+            &self.target,
+        )
+    }
+}
+
+/// Median of the values, which aren't empty
+#[cfg(not(target_arch = "wasm32"))]
+fn median(values: &[f64]) -> f64 {
+    let mut values = values.to_vec();
+    values.sort_by(f64::total_cmp);
+    // the two middle values of an even number of values, the same one twice otherwise
+    let len = values.len();
+    (values[(len - 1) / 2] + values[len / 2]) / 2.0
+}
+
+/// Change of a run from its baseline, as a fraction
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Copy, Clone, PartialEq, Debug)]
+struct Change {
+    fraction: f64,
+    /// Whether the change is larger than the threshold
+    step: bool,
+}
+
+/// Each run is compared against the median of the earlier runs in a window, so a single noisy run
+/// doesn't flag the one after it as well. A step change starts a new window so the runs after the
+/// step are compared against it.
+#[cfg(not(target_arch = "wasm32"))]
+struct Baseline {
+    /// The earlier runs of the series since the last step change, the latest one last
+    window: Vec<f64>,
+    size: usize,
+    threshold: f64,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Baseline {
+    fn new(size: usize, threshold: f64) -> Self {
+        Self {
+            window: vec![],
+            size: size.max(1),
+            threshold,
+        }
+    }
+
+    /// Compares the next run of the series against the earlier ones. The first run has nothing
+    /// to compare against, and a baseline of zero has no relative change, as with results too
+    /// small for their precision
+    fn next(&mut self, value: f64) -> Option<Change> {
+        let baseline = if self.window.is_empty() {
+            0.0
+        } else {
+            median(&self.window)
+        };
+        let change = if baseline > 0.0 {
+            let fraction = value / baseline - 1.0;
+            Some(Change {
+                fraction,
+                step: fraction.abs() > self.threshold,
+            })
+        } else {
+            None
+        };
+
+        if matches!(change, Some(Change { step: true, .. })) {
+            self.window.clear();
+        }
+        self.window.push(value);
+        if self.window.len() > self.size {
+            self.window.remove(0);
+        }
+        change
+    }
+}
+
+/// Prints each result in the database that matches the filter over the runs, oldest first,
+/// comparing each run against a `Baseline`. Step changes are flagged with what changed in the
+/// build since the previous run.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn run(path: &Path, trends: &Trends) {
+    if !path.exists() {
+        eprintln!("no history database at {}", path.display());
+        std::process::exit(1);
+    }
+
+    let result = (|| -> rusqlite::Result<()> {
+        let connection = Connection::open(path)?;
+        let mut statement = connection.prepare(
+            "SELECT results.name, results.cache, results.metric, runs.cpu_model, runs.target,
+             runs.id, datetime(runs.timestamp, 'unixepoch'), runs.git_commit, runs.rustc,
+             runs.features, results.value
+             FROM results JOIN runs ON runs.id = results.run
+             WHERE runs.tool = ?1 AND instr(results.name, ?2) > 0
+             ORDER BY results.name, results.cache, results.metric, runs.cpu_model, runs.target,
+             runs.timestamp, runs.id",
+        )?;
+        let rows = statement
+            .query_map(params![trends.tool, trends.filter.unwrap_or("")], |row| {
+                Ok(Row {
+                    name: row.get(0)?,
+                    cache: row.get(1)?,
+                    metric: row.get(2)?,
+                    cpu_model: row.get(3)?,
+                    target: row.get(4)?,
+                    run: row.get(5)?,
+                    date: row.get(6)?,
+                    git_commit: row.get(7)?,
+                    rustc: row.get(8)?,
+                    features: row.get(9)?,
+                    value: row.get(10)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        if rows.is_empty() {
+            println!("no recorded results");
+            return Ok(());
+        }
+
+        let mut baseline = Baseline::new(trends.window, trends.threshold);
+        let mut previous: Option<&Row> = None;
+        for row in &rows {
+            if !matches!(previous, Some(p) if p.series() == row.series()) {
+                baseline = Baseline::new(trends.window, trends.threshold);
+                println!();
+                println!(
+                    "{} {}, {} ({}, {})",
+                    row.name, row.metric, row.cache, row.cpu_model, row.target
+                );
+            }
+
+            print!(
+                "  {}  {:8}  {:8}  {:>9.1}",
+                row.date,
+                row.git_commit.get(..8).unwrap_or(&row.git_commit),
+                row.rustc.split(' ').nth(1).unwrap_or(&row.rustc),
+                row.value
+            );
+            if let Some(change) = baseline.next(row.value) {
+                print!("  {:>+6.1}%", change.fraction * 100.0);
+                if change.step {
+                    // there's a baseline, so the previous row is an earlier run of the series
+                    let previous = previous.unwrap();
+                    print!(
+                        "  step: {}",
+                        changes(&connection, previous, row)?.join(", ")
+                    );
+                }
+            }
+            println!();
+            previous = Some(row);
+        }
+        Ok(())
+    })();
+
+    if let Err(err) = result {
+        eprintln!("failed to read {}: {}", path.display(), err);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn run(_path: &Path, _trends: &Trends) {
+    eprintln!("the history database isn't supported on wasm32");
+    std::process::exit(1);
+}
+
+/// Most crate version changes listed for a step change
+#[cfg(not(target_arch = "wasm32"))]
+const MAX_CRATE_CHANGES: usize = 4;
+
+/// What changed in the build between two runs
+#[cfg(not(target_arch = "wasm32"))]
+fn changes(connection: &Connection, from: &Row, to: &Row) -> rusqlite::Result<Vec<String>> {
+    let mut changes = vec![];
+    if from.git_commit != to.git_commit {
+        changes.push(format!(
+            "commit {} -> {}",
+            from.git_commit.get(..8).unwrap_or(&from.git_commit),
+            to.git_commit.get(..8).unwrap_or(&to.git_commit)
+        ));
+    }
+    if from.rustc != to.rustc {
+        changes.push(format!("{} -> {}", from.rustc, to.rustc));
+    }
+    if from.features != to.features {
+        changes.push(format!("features {:?} -> {:?}", from.features, to.features));
+    }
+
+    // crates added, removed or at other versions between the runs
+    let (from_crates, to_crates) = (crates(connection, from.run)?, crates(connection, to.run)?);
+    let crates = from_crates
+        .keys()
+        .chain(to_crates.keys())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter(|name| from_crates.get(*name) != to_crates.get(*name))
+        .map(|name| {
+            let versions = |crates: &BTreeMap<String, Vec<String>>| match crates.get(name) {
+                Some(versions) => versions.join(" "),
+                None => "none".to_string(),
+            };
+            format!(
+                "{} {} -> {}",
+                name,
+                versions(&from_crates),
+                versions(&to_crates)
+            )
+        })
+        .collect::<Vec<_>>();
+    changes.extend(crates.iter().take(MAX_CRATE_CHANGES).cloned());
+    if crates.len() > MAX_CRATE_CHANGES {
+        changes.push(format!("{} more crates", crates.len() - MAX_CRATE_CHANGES));
+    }
+
+    if changes.is_empty() {
+        changes.push("no build changes".to_string());
+    }
+    Ok(changes)
+}
+
+/// Versions of each crate the build of a run depended on
+#[cfg(not(target_arch = "wasm32"))]
+fn crates(connection: &Connection, run: i64) -> rusqlite::Result<BTreeMap<String, Vec<String>>> {
+    let mut statement = connection
+        .prepare("SELECT name, version FROM crates WHERE run = ?1 ORDER BY name, version")?;
+    let mut crates = BTreeMap::<String, Vec<String>>::new();
+    for row in statement.query_map(params![run], |row| Ok((row.get(0)?, row.get(1)?)))? {
+        let (name, version) = row?;
+        crates.entry(name).or_default().push(version);
+    }
+    Ok(crates)
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window() {
+        assert_eq!(parse_window("5"), Ok(5));
+        assert!(parse_window("0").is_err());
+        assert!(parse_window("-1").is_err());
+        assert!(parse_window("").is_err());
+    }
+
+    #[test]
+    fn median_of_even_window() {
+        assert_eq!(median(&[4.0, 1.0, 3.0, 2.0]), 2.5);
+        assert_eq!(median(&[3.0, 1.0, 2.0]), 2.0);
+        assert_eq!(median(&[5.0]), 5.0);
+    }
+
+    /// Changes from the baseline of each run, in percent, and whether they're steps
+    fn changes(baseline: &mut Baseline, values: &[f64]) -> Vec<Option<(i64, bool)>> {
+        values
+            .iter()
+            .map(|&value| {
+                baseline
+                    .next(value)
+                    .map(|c| ((c.fraction * 100.0).round() as i64, c.step))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn baseline_is_median_of_window() {
+        let mut baseline = Baseline::new(4, 0.1);
+        assert_eq!(
+            changes(&mut baseline, &[100.0, 104.0, 96.0, 98.0, 102.0]),
+            vec![
+                None,
+                Some((4, false)),
+                // median of 100 and 104
+                Some((-6, false)),
+                Some((-2, false)),
+                // median of 100, 104, 96 and 98
+                Some((3, false)),
+            ]
+        );
+
+        // only the last runs are in the window, the first run is left out of the last median
+        let mut baseline = Baseline::new(2, 0.5);
+        assert_eq!(
+            changes(&mut baseline, &[100.0, 140.0, 160.0, 150.0]),
+            vec![None, Some((40, false)), Some((33, false)), Some((0, false))]
+        );
+    }
+
+    #[test]
+    fn zero_baseline_is_skipped() {
+        let mut baseline = Baseline::new(1, 0.1);
+        assert_eq!(
+            changes(&mut baseline, &[0.0, 50.0, 0.0, 0.0, 60.0]),
+            vec![None, None, Some((-100, true)), None, None]
+        );
+    }
+
+    #[test]
+    fn step_resets_window() {
+        let mut baseline = Baseline::new(5, 0.1);
+        assert_eq!(
+            changes(&mut baseline, &[100.0, 100.0, 100.0, 150.0, 150.0, 152.0]),
+            vec![
+                None,
+                Some((0, false)),
+                Some((0, false)),
+                Some((50, true)),
+                // compared against the run of the step, not the median of all four
+                Some((0, false)),
+                Some((1, false)),
+            ]
+        );
+    }
+}
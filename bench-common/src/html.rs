@@ -0,0 +1,23 @@
+//! The HTML document the charts of the `--html` reports are written into
+
+/// An HTML document with the charts inlined, so it can be shared as a single file. The title and
+/// headings are escaped, the SVG of each section is inlined as is, plotters escapes its own text.
+pub fn document(title: &str, sections: &[(String, String)]) -> String {
+    let title = escape(title);
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <style>body {{ font-family: sans-serif; }}</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+        title, title
+    );
+    for (heading, svg) in sections {
+        html.push_str(&format!("<h2>{}</h2>\n{}\n", escape(heading), svg));
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,10 @@
+//! Measurement and reporting code shared by test-hash and test-compress, and reading their
+//! results back
+
+pub mod cache;
+pub mod counters;
+pub mod history;
+pub mod html;
+pub mod profile;
+pub mod results;
+pub mod simd;
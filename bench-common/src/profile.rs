@@ -0,0 +1,99 @@
+//! Sampling CPU profiles of each benchmark with pprof, written as a flamegraph SVG and a pprof
+//! protobuf per benchmark
+
+use std::path::Path;
+
+#[cfg(unix)]
+pub struct Profiler {
+    dir: std::path::PathBuf,
+}
+
+#[cfg(unix)]
+impl Profiler {
+    /// Samples per second, not a multiple of common timer frequencies so samples don't line up
+    /// with periodic work
+    const FREQUENCY: i32 = 997;
+
+    /// Time the benchmark is repeated for while profiling, for enough samples of fast benchmarks
+    const DURATION: std::time::Duration = std::time::Duration::from_secs(1);
+
+    pub fn new(dir: &Path) -> Result<Self, String> {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("failed to create {}: {}", dir.display(), e))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+        })
+    }
+
+    /// Repeats `f` with the profiler running, separately from the timed runs so the sampling
+    /// doesn't affect them, and writes `<name>.svg` and `<name>.pb`
+    pub fn profile(&self, name: &str, mut f: impl FnMut()) {
+        use pprof::protos::Message;
+
+        let guard = pprof::ProfilerGuardBuilder::default()
+            .frequency(Self::FREQUENCY)
+            .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+            .build()
+            .unwrap();
+
+        let start_time = std::time::Instant::now();
+        while start_time.elapsed() < Self::DURATION {
+            f();
+        }
+
+        let report = guard.report().build().unwrap();
+        let name = file_name(name);
+
+        let svg = std::fs::File::create(self.dir.join(format!("{}.svg", name))).unwrap();
+        report.flamegraph(svg).unwrap();
+
+        let profile = report.pprof().unwrap().write_to_bytes().unwrap();
+        std::fs::write(self.dir.join(format!("{}.pb", name)), profile).unwrap();
+    }
+}
+
+#[cfg(not(unix))]
+pub enum Profiler {}
+
+#[cfg(not(unix))]
+impl Profiler {
+    pub fn new(_dir: &Path) -> Result<Self, String> {
+        Err("profiling is only supported on Unix".to_string())
+    }
+
+    pub fn profile(&self, _name: &str, _f: impl FnMut()) {
+        match *self {}
+    }
+}
+
+/// Profiles `f` as `name` if profiling
+pub fn profile(profiler: &Option<Profiler>, name: &str, f: impl FnMut()) {
+    if let Some(profiler) = profiler {
+        profiler.profile(name, f);
+    }
+}
+
+/// Opens the profile directory if asked for, exiting with the error if it can't be
+pub fn open(dir: Option<&Path>) -> Option<Profiler> {
+    match Profiler::new(dir?) {
+        Ok(profiler) => Some(profiler),
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Keeps letters, digits, `-` and `_` of the name, replacing anything else with `-`
+#[cfg(unix)]
+fn file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,160 @@
+//! Reading back the CSV output of test-hash and test-compress, to compare runs of them
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Tool {
+    Hash,
+    Compress,
+}
+
+impl Tool {
+    /// Columns identifying a result, which runs are compared on
+    pub fn key_columns(self) -> &'static [&'static str] {
+        match self {
+            Tool::Hash => &["hash", "implementation", "cache"],
+            Tool::Compress => &["data", "source", "codec", "cache"],
+        }
+    }
+
+    /// Single-threaded speeds, the multithreaded ones depend too much on the machine and don't
+    /// exist for WASM builds
+    pub fn metrics(self) -> &'static [&'static str] {
+        match self {
+            Tool::Hash => &["MB/s"],
+            Tool::Compress => &["compress MB/s", "decompress MB/s"],
+        }
+    }
+}
+
+/// One speed of a hash or codec in a run
+#[derive(Clone, PartialEq, Debug)]
+pub struct Record {
+    /// Values of the key columns of the tool
+    pub key: Vec<String>,
+    pub metric: &'static str,
+    pub mb_per_s: f64,
+}
+
+/// Looks up the key columns of a result, results from before cache modes were added are warm
+pub fn key(tool: Tool, value: impl Fn(&str) -> Option<String>) -> Result<Vec<String>, String> {
+    tool.key_columns()
+        .iter()
+        .map(|&column| match value(column) {
+            Some(value) => Ok(value),
+            None if column == "cache" => Ok("warm".to_string()),
+            None => Err(format!("no {} in result", column)),
+        })
+        .collect()
+}
+
+/// Parses a speed in MB/s
+pub fn parse_speed(value: &str) -> Result<f64, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{:?} isn't a speed", value))
+}
+
+/// Reads CSV output, telling the tools apart by test-compress's `codec` column. Fields may be
+/// quoted, and rows with more or fewer fields than the header are errors
+pub fn parse_csv(csv: &str) -> Result<(Tool, Vec<Record>), String> {
+    let mut reader = csv::Reader::from_reader(csv.as_bytes());
+    let header = reader.headers().map_err(|e| e.to_string())?.clone();
+    if header.is_empty() {
+        return Err("no CSV header".to_string());
+    }
+    let tool = if header.iter().any(|c| c == "codec") {
+        Tool::Compress
+    } else {
+        Tool::Hash
+    };
+
+    let mut records = vec![];
+    for values in reader.records() {
+        let values = values.map_err(|e| e.to_string())?;
+        let value = |column: &str| {
+            let i = header.iter().position(|c| c == column)?;
+            values.get(i).map(|v| v.to_string())
+        };
+        let key = key(tool, value)?;
+        for &metric in tool.metrics() {
+            let speed = value(metric).ok_or_else(|| format!("no {} column", metric))?;
+            records.push(Record {
+                key: key.clone(),
+                metric,
+                mb_per_s: parse_speed(&speed)?,
+            });
+        }
+    }
+    Ok((tool, records))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(key: &[&str], metric: &'static str, mb_per_s: f64) -> Record {
+        Record {
+            key: key.iter().map(|k| k.to_string()).collect(),
+            metric,
+            mb_per_s,
+        }
+    }
+
+    #[test]
+    fn csv() {
+        let cases = [
+            (
+                "implementation,hash,MB/s,MT MB/s,MT ratio,cache,backend\n\
+                 blake3,BLAKE3,4518.25,4812.00,1.065,warm,avx2\n\
+                 blake3,BLAKE3,1200.50,1300.00,1.083,cold,avx2\n",
+                Tool::Hash,
+                vec![
+                    record(&["BLAKE3", "blake3", "warm"], "MB/s", 4518.25),
+                    record(&["BLAKE3", "blake3", "cold"], "MB/s", 1200.5),
+                ],
+            ),
+            // quoted fields, and results from before the cache column are warm
+            (
+                "implementation,hash,MB/s\n\"hmac, sha2\",HMAC-SHA256,\"512.5\"\n",
+                Tool::Hash,
+                vec![record(
+                    &["HMAC-SHA256", "hmac, sha2", "warm"],
+                    "MB/s",
+                    512.5,
+                )],
+            ),
+            (
+                "data,source,codec,backend,ratio,cache,compress MB/s,decompress MB/s\n\
+                 json,lz4-flex,lz4,unsafe,4.200,warm,800.00,3000.00\n",
+                Tool::Compress,
+                vec![
+                    record(&["json", "lz4-flex", "lz4", "warm"], "compress MB/s", 800.0),
+                    record(
+                        &["json", "lz4-flex", "lz4", "warm"],
+                        "decompress MB/s",
+                        3000.0,
+                    ),
+                ],
+            ),
+            // a header without results
+            ("implementation,hash,MB/s\n", Tool::Hash, vec![]),
+        ];
+
+        for (csv, tool, records) in &cases {
+            assert_eq!(parse_csv(csv), Ok((*tool, records.clone())), "{:?}", csv);
+        }
+    }
+
+    #[test]
+    fn invalid_csv() {
+        for csv in &[
+            "",
+            // ragged rows, such as a name with an unquoted comma
+            "implementation,hash,MB/s\nhmac, sha2,HMAC-SHA256,512\n",
+            "implementation,hash,MB/s\nblake3,BLAKE3\n",
+            "implementation,hash,MB/s\nblake3,BLAKE3,fast\n",
+            "implementation,hash\nblake3,BLAKE3\n",
+        ] {
+            assert!(parse_csv(csv).is_err(), "{:?}", csv);
+        }
+    }
+}
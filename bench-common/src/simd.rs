@@ -0,0 +1,51 @@
+//! CPU features detected at runtime, or fixed when compiling for WebAssembly
+
+/// Names of the SIMD and crypto CPU features the hashes and codecs can use, that the CPU has
+pub fn cpu_features() -> Vec<&'static str> {
+    let mut features = vec![];
+
+    macro_rules! detect {
+        ($is_detected:ident, $($feature:tt),*) => {
+            $(
+                if std::arch::$is_detected!($feature) {
+                    features.push($feature);
+                }
+            )*
+        };
+    }
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    detect!(
+        is_x86_feature_detected,
+        "sse2",
+        "ssse3",
+        "sse4.1",
+        "sse4.2",
+        "avx",
+        "avx2",
+        "avx512f",
+        "avx512vl",
+        "aes",
+        "pclmulqdq",
+        "sha",
+        "bmi2"
+    );
+
+    #[cfg(target_arch = "aarch64")]
+    detect!(
+        is_aarch64_feature_detected,
+        "neon",
+        "aes",
+        "pmull",
+        "sha2",
+        "sha3",
+        "crc"
+    );
+
+    // WebAssembly features are fixed when compiling
+    if cfg!(target_feature = "simd128") {
+        features.push("simd128");
+    }
+
+    features
+}
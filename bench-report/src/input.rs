@@ -0,0 +1,302 @@
+//! Reads the results of test-hash and test-compress runs from their CSV, JSON or text output
+
+use bench_common::results::{key, parse_csv, parse_speed, Record, Tool};
+use std::path::Path;
+
+pub struct Run {
+    pub name: String,
+    pub tool: Tool,
+    pub records: Vec<Record>,
+}
+
+/// Reads a result file, as JSON or CSV by its extension and as text otherwise
+pub fn read(name: String, path: &Path) -> Result<Run, String> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+    let (tool, records) = match path.extension().and_then(|e| e.to_str()) {
+        Some("json") => parse_json(&contents),
+        Some("csv") => parse_csv(&contents),
+        _ => parse_text(&contents),
+    }
+    .map_err(|e| format!("failed to parse {}: {}", path.display(), e))?;
+
+    if records.is_empty() {
+        return Err(format!("no results in {}", path.display()));
+    }
+    Ok(Run {
+        name,
+        tool,
+        records,
+    })
+}
+
+/// Reads the results of a test-hash or test-compress JSON report, telling them apart by the tool
+/// in its metadata
+fn parse_json(contents: &str) -> Result<(Tool, Vec<Record>), String> {
+    let report: serde_json::Value = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+    let (tool, fields) = match report["metadata"]["tool"].as_str() {
+        Some("test-hash") => (Tool::Hash, &["mb_per_s"][..]),
+        Some("test-compress") => (
+            Tool::Compress,
+            &["compress_mb_per_s", "decompress_mb_per_s"][..],
+        ),
+        Some(tool) => return Err(format!("unknown tool {:?} in the report", tool)),
+        None => return Err("no tool in the report metadata".to_string()),
+    };
+    let results = report["results"]
+        .as_array()
+        .ok_or("no results array in the report")?;
+
+    let mut records = vec![];
+    for result in results {
+        let key = key(tool, |column| {
+            result[column].as_str().map(|v| v.to_string())
+        })?;
+        for (&metric, &field) in tool.metrics().iter().zip(fields) {
+            let mb_per_s = result[field]
+                .as_f64()
+                .ok_or_else(|| format!("no {} in result", field))?;
+            records.push(Record {
+                key: key.clone(),
+                metric,
+                mb_per_s,
+            });
+        }
+    }
+    Ok((tool, records))
+}
+
+/// Speeds on a line of text output, grouped by the `warm:` and `cold:` cache mode labels. Lines
+/// without labels are warm.
+fn text_speeds(tokens: &[&str]) -> Result<Vec<(String, Vec<f64>)>, String> {
+    let mut modes = vec![("warm".to_string(), vec![])];
+    for (i, token) in tokens.iter().enumerate() {
+        match token.strip_suffix(':') {
+            Some(mode @ "warm") | Some(mode @ "cold") => {
+                if matches!(modes.last(), Some((_, speeds)) if speeds.is_empty()) {
+                    modes.pop();
+                }
+                modes.push((mode.to_string(), vec![]));
+            }
+            _ if *token == "MB/s" || *token == "MB/s," => {
+                let speed = parse_speed(i.checked_sub(1).map_or("", |i| tokens[i]))?;
+                modes.last_mut().unwrap().1.push(speed);
+            }
+            _ => {}
+        }
+    }
+    modes.retain(|(_, speeds)| !speeds.is_empty());
+    Ok(modes)
+}
+
+/// Reads the lines of text output with speeds. test-compress output is recognized by its
+/// `----- data:` headers, which name the dataset of the lines after them.
+fn parse_text(contents: &str) -> Result<(Tool, Vec<Record>), String> {
+    let tool = if contents.lines().any(|line| line.starts_with("----- data:")) {
+        Tool::Compress
+    } else {
+        Tool::Hash
+    };
+
+    let mut data = String::new();
+    let mut records = vec![];
+    for line in contents.lines() {
+        let tokens = line.split_whitespace().collect::<Vec<_>>();
+        if line.starts_with("----- data:") {
+            data = tokens.get(2).ok_or("no dataset name")?.to_string();
+            continue;
+        }
+
+        for (mode, speeds) in text_speeds(&tokens)? {
+            let value = |column: &str| match column {
+                "hash" | "source" => Some(tokens[0].to_string()),
+                "implementation" | "codec" => Some(tokens[1].to_string()),
+                "data" => Some(data.clone()),
+                "cache" => Some(mode.clone()),
+                _ => None,
+            };
+            let key = key(tool, value)?;
+
+            // test-compress shows singlethreaded and multithreaded speeds with --parallel
+            let metrics = match (tool, speeds.len()) {
+                (Tool::Compress, 4) => vec![speeds[0], speeds[2]],
+                (Tool::Compress, 2) => speeds,
+                (Tool::Compress, _) => return Err(format!("unexpected speeds in {:?}", line)),
+                (Tool::Hash, _) => vec![speeds[0]],
+            };
+            for (&metric, mb_per_s) in tool.metrics().iter().zip(metrics) {
+                records.push(Record {
+                    key: key.clone(),
+                    metric,
+                    mb_per_s,
+                });
+            }
+        }
+    }
+    Ok((tool, records))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(key: &[&str], metric: &'static str, mb_per_s: f64) -> Record {
+        Record {
+            key: key.iter().map(|k| k.to_string()).collect(),
+            metric,
+            mb_per_s,
+        }
+    }
+
+    /// Speeds of each cache mode on a line
+    type ModeSpeeds = &'static [(&'static str, &'static [f64])];
+
+    #[test]
+    fn speeds_by_cache_mode() {
+        let cases: &[(&str, ModeSpeeds)] = &[
+            (
+                "BLAKE3 blake3 avx2 4518 MB/s 12000 MB/s 2.7x",
+                &[("warm", &[4518.0, 12000.0])],
+            ),
+            (
+                "BLAKE3 blake3 avx2 warm: 4518 MB/s 12000 MB/s 2.7x cold: 1200 MB/s 3000 MB/s 2.5x",
+                &[("warm", &[4518.0, 12000.0]), ("cold", &[1200.0, 3000.0])],
+            ),
+            (
+                "snap snappy scalar 1.68x cold: 1028 MB/s 1749 MB/s",
+                &[("cold", &[1028.0, 1749.0])],
+            ),
+            (
+                "snap snappy scalar 1.68x 1028 MB/s 4000 MB/s, 3.9x 1749 MB/s 6000 MB/s, 3.4x",
+                &[("warm", &[1028.0, 4000.0, 1749.0, 6000.0])],
+            ),
+            ("----- data: json ------", &[]),
+            ("", &[]),
+        ];
+
+        for (line, expected) in cases {
+            let tokens = line.split_whitespace().collect::<Vec<_>>();
+            let expected = expected
+                .iter()
+                .map(|(mode, speeds)| (mode.to_string(), speeds.to_vec()))
+                .collect::<Vec<_>>();
+            assert_eq!(text_speeds(&tokens), Ok(expected), "{:?}", line);
+        }
+
+        assert!(text_speeds(&["MB/s"]).is_err());
+        assert!(text_speeds(&["BLAKE3", "blake3", "fast", "MB/s"]).is_err());
+    }
+
+    #[test]
+    fn text() {
+        let (tool, records) = parse_text(
+            "CPU features: sse2 avx2\n\
+             BLAKE3               blake3               avx2                  4518 MB/s  12000 MB/s   2.7x\n\
+             SHA-256              sha2                 sha-ni                 1800 MB/s   9000 MB/s   5.0x\n",
+        )
+        .unwrap();
+        assert_eq!(tool, Tool::Hash);
+        assert_eq!(
+            records,
+            vec![
+                record(&["BLAKE3", "blake3", "warm"], "MB/s", 4518.0),
+                record(&["SHA-256", "sha2", "warm"], "MB/s", 1800.0),
+            ]
+        );
+
+        let (tool, records) = parse_text(
+            "----- data: json    ----------------------------------------\n\
+             snap                 snappy       scalar       4.70x warm:  1211 MB/s  1618 MB/s cold:   900 MB/s  1400 MB/s\n",
+        )
+        .unwrap();
+        assert_eq!(tool, Tool::Compress);
+        assert_eq!(
+            records,
+            vec![
+                record(&["json", "snap", "snappy", "warm"], "compress MB/s", 1211.0),
+                record(
+                    &["json", "snap", "snappy", "warm"],
+                    "decompress MB/s",
+                    1618.0
+                ),
+                record(&["json", "snap", "snappy", "cold"], "compress MB/s", 900.0),
+                record(
+                    &["json", "snap", "snappy", "cold"],
+                    "decompress MB/s",
+                    1400.0
+                ),
+            ]
+        );
+
+        // empty output has no results, which reading a file reports as an error
+        assert_eq!(parse_text(""), Ok((Tool::Hash, vec![])));
+    }
+
+    #[test]
+    fn invalid_text() {
+        for text in &[
+            // a test-compress line cut short, with a compression but no decompression speed
+            "----- data: json ---\nsnap snappy scalar 4.70x 1211 MB/s\n",
+            "----- data:\nsnap snappy scalar 4.70x 1211 MB/s 1618 MB/s\n",
+        ] {
+            assert!(parse_text(text).is_err(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn json() {
+        let (tool, records) = parse_json(
+            r#"{
+                "metadata": { "tool": "test-hash" },
+                "results": [
+                    { "implementation": "blake3", "hash": "BLAKE3", "cache": "cold", "mb_per_s": 1200.5 },
+                    { "implementation": "sha2", "hash": "SHA-256", "mb_per_s": 1800.0 }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(tool, Tool::Hash);
+        assert_eq!(
+            records,
+            vec![
+                record(&["BLAKE3", "blake3", "cold"], "MB/s", 1200.5),
+                record(&["SHA-256", "sha2", "warm"], "MB/s", 1800.0),
+            ]
+        );
+
+        let (tool, records) = parse_json(
+            r#"{
+                "metadata": { "tool": "test-compress" },
+                "results": [
+                    {
+                        "data": "json", "source": "snap", "codec": "snappy", "cache": "warm",
+                        "compress_mb_per_s": 1211.0, "decompress_mb_per_s": 1618.0
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(tool, Tool::Compress);
+        assert_eq!(
+            records,
+            vec![
+                record(&["json", "snap", "snappy", "warm"], "compress MB/s", 1211.0),
+                record(
+                    &["json", "snap", "snappy", "warm"],
+                    "decompress MB/s",
+                    1618.0
+                ),
+            ]
+        );
+
+        for json in &[
+            "",
+            r#"{ "results": [] }"#,
+            r#"{ "metadata": { "tool": "test-hash" } }"#,
+            r#"{ "metadata": { "tool": "test-hash" }, "results": [{ "hash": "BLAKE3", "mb_per_s": 1 }] }"#,
+            r#"{ "metadata": { "tool": "test-compress" }, "results": [{ "data": "json", "source": "snap", "codec": "snappy", "compress_mb_per_s": 1 }] }"#,
+        ] {
+            assert!(parse_json(json).is_err(), "{:?}", json);
+        }
+    }
+}
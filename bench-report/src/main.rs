@@ -0,0 +1,75 @@
+//! Joins the results of several test-hash or test-compress runs, such as native and WASM runs or
+//! runs on different machines, into a table comparing their speeds
+
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+mod input;
+mod table;
+
+#[derive(StructOpt)]
+#[structopt(about = "Compares the results of test-hash or test-compress runs")]
+struct Options {
+    /// Result files, CSV or JSON by their extension and text output otherwise. Runs are named after
+    /// the file, or given a name with name=path
+    #[structopt(required = true)]
+    files: Vec<String>,
+
+    /// Name of the run the others are relative to, defaults to the first
+    #[structopt(long)]
+    baseline: Option<String>,
+
+    /// Relative speeds further from 1 than this are highlighted as faster or slower
+    #[structopt(long, default_value = "0.05")]
+    threshold: f64,
+
+    /// Output format, HTML is a self-contained document
+    #[structopt(long, default_value = "Text", possible_values = &table::Output::variants(), case_insensitive = true)]
+    output: table::Output,
+}
+
+fn main() {
+    let options = Options::from_args();
+
+    let runs = options
+        .files
+        .iter()
+        .map(|file| {
+            let (name, path) = match file.split_once('=') {
+                Some((name, path)) => (name.to_string(), PathBuf::from(path)),
+                None => {
+                    let path = PathBuf::from(file);
+                    let name = path.file_stem().unwrap_or_default().to_string_lossy();
+                    (name.into_owned(), path)
+                }
+            };
+            input::read(name, &path)
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        });
+
+    let tool = runs[0].tool;
+    if let Some(run) = runs.iter().find(|run| run.tool != tool) {
+        eprintln!(
+            "{} has results of a different tool than {}",
+            run.name, runs[0].name
+        );
+        std::process::exit(1);
+    }
+
+    let baseline = match &options.baseline {
+        Some(name) => match runs.iter().position(|run| &run.name == name) {
+            Some(baseline) => baseline,
+            None => {
+                eprintln!("no run named {}", name);
+                std::process::exit(1);
+            }
+        },
+        None => 0,
+    };
+
+    table::Table::new(tool, &runs, baseline, options.threshold).print(options.output);
+}
@@ -0,0 +1,261 @@
+//! Joins the runs into a comparison table and renders it for the terminal, Markdown or HTML
+
+use crate::input::Run;
+use bench_common::results::Tool;
+use structopt::clap::arg_enum;
+
+arg_enum! {
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Output {
+    Text,
+    Markdown,
+    Html,
+}
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Change {
+    Faster,
+    Slower,
+    Same,
+}
+
+struct Cell {
+    text: String,
+    change: Change,
+}
+
+impl Cell {
+    fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            change: Change::Same,
+        }
+    }
+
+    /// Speed relative to the baseline, highlighted when it differs by more than the threshold
+    fn relative(relative: f64, threshold: f64) -> Self {
+        let change = if relative > 1.0 + threshold {
+            Change::Faster
+        } else if relative < 1.0 - threshold {
+            Change::Slower
+        } else {
+            Change::Same
+        };
+        Self {
+            text: format!("{:.2}x", relative),
+            change,
+        }
+    }
+}
+
+pub struct Table {
+    header: Vec<String>,
+    /// Number of leading key and metric columns, which are left aligned
+    label_columns: usize,
+    rows: Vec<Vec<Cell>>,
+}
+
+impl Table {
+    /// Joins the runs on their key columns and metric, in the order results first appear. Each run
+    /// gets a speed column and, except the baseline, a column of its speed relative to the baseline.
+    /// Results missing from a run are shown as `-`.
+    pub fn new(tool: Tool, runs: &[Run], baseline: usize, threshold: f64) -> Self {
+        let mut header = tool
+            .key_columns()
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>();
+        header.push("metric".to_string());
+        let label_columns = header.len();
+        for run in runs {
+            header.push(format!("{} MB/s", run.name));
+        }
+        for (i, run) in runs.iter().enumerate() {
+            if i != baseline {
+                header.push(format!("{} rel", run.name));
+            }
+        }
+
+        let mut keys = Vec::<(&[String], &str)>::new();
+        for record in runs.iter().flat_map(|run| &run.records) {
+            let key = (record.key.as_slice(), record.metric);
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+
+        let mut relatives = vec![vec![]; runs.len()];
+        let mut rows = vec![];
+        for (key, metric) in keys {
+            let speeds = runs
+                .iter()
+                .map(|run| {
+                    run.records
+                        .iter()
+                        .find(|r| r.key == key && r.metric == metric)
+                        .map(|r| r.mb_per_s)
+                })
+                .collect::<Vec<_>>();
+
+            let mut row = key.iter().map(Cell::new).collect::<Vec<_>>();
+            row.push(Cell::new(metric));
+            for speed in &speeds {
+                row.push(Cell::new(match speed {
+                    Some(speed) => format!("{:.0}", speed),
+                    None => "-".to_string(),
+                }));
+            }
+            for (i, speed) in speeds.iter().enumerate() {
+                if i == baseline {
+                    continue;
+                }
+                row.push(match (speed, speeds[baseline]) {
+                    (Some(speed), Some(baseline_speed)) => {
+                        relatives[i].push(speed / baseline_speed);
+                        Cell::relative(speed / baseline_speed, threshold)
+                    }
+                    _ => Cell::new("-"),
+                });
+            }
+            rows.push(row);
+        }
+
+        // geometric means of the relative speeds, over the results in both runs
+        let mut row = (0..label_columns + runs.len())
+            .map(|_| Cell::new(""))
+            .collect::<Vec<_>>();
+        row[0] = Cell::new("geometric mean");
+        for (i, relatives) in relatives.iter().enumerate() {
+            if i == baseline {
+                continue;
+            }
+            row.push(if relatives.is_empty() {
+                Cell::new("-")
+            } else {
+                let mean =
+                    (relatives.iter().map(|r| r.ln()).sum::<f64>() / relatives.len() as f64).exp();
+                Cell::relative(mean, threshold)
+            });
+        }
+        rows.push(row);
+
+        Self {
+            header,
+            label_columns,
+            rows,
+        }
+    }
+
+    pub fn print(&self, output: Output) {
+        match output {
+            Output::Text => self.print_text(),
+            Output::Markdown => self.print_markdown(),
+            Output::Html => self.print_html(),
+        }
+    }
+
+    fn print_text(&self) {
+        let widths = (0..self.header.len())
+            .map(|i| {
+                self.rows
+                    .iter()
+                    .map(|row| row[i].text.len())
+                    .chain(std::iter::once(self.header[i].len()))
+                    .max()
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        let line = |cells: Vec<&str>| {
+            let cells = cells
+                .iter()
+                .enumerate()
+                .map(|(i, cell)| {
+                    if i < self.label_columns {
+                        format!("{:width$}", cell, width = widths[i])
+                    } else {
+                        format!("{:>width$}", cell, width = widths[i])
+                    }
+                })
+                .collect::<Vec<_>>();
+            println!("{}", cells.join("  ").trim_end());
+        };
+
+        line(self.header.iter().map(|h| h.as_str()).collect());
+        for row in &self.rows {
+            line(row.iter().map(|c| c.text.as_str()).collect());
+        }
+    }
+
+    fn print_markdown(&self) {
+        println!("| {} |", self.header.join(" | "));
+        let alignments = (0..self.header.len())
+            .map(|i| if i < self.label_columns { "---" } else { "--:" })
+            .collect::<Vec<_>>();
+        println!("| {} |", alignments.join(" | "));
+        for row in &self.rows {
+            let cells = row
+                .iter()
+                .map(|c| match c.change {
+                    Change::Same => c.text.clone(),
+                    _ => format!("**{}**", c.text),
+                })
+                .collect::<Vec<_>>();
+            println!("| {} |", cells.join(" | "));
+        }
+    }
+
+    /// A self-contained HTML document, with faster results in green and slower ones in red
+    fn print_html(&self) {
+        println!("<!DOCTYPE html>");
+        println!("<html>");
+        println!("<head>");
+        println!("<meta charset=\"utf-8\">");
+        println!("<title>bench-report</title>");
+        println!("<style>");
+        println!("body {{ font-family: sans-serif; }}");
+        println!("table {{ border-collapse: collapse; }}");
+        println!("th, td {{ padding: 2px 8px; border-bottom: 1px solid #ddd; }}");
+        println!("td.number {{ text-align: right; font-variant-numeric: tabular-nums; }}");
+        println!("td.faster {{ color: #080; }}");
+        println!("td.slower {{ color: #c00; }}");
+        println!("</style>");
+        println!("</head>");
+        println!("<body>");
+        println!("<table>");
+
+        let header = self
+            .header
+            .iter()
+            .map(|h| format!("<th>{}</th>", escape(h)))
+            .collect::<String>();
+        println!("<tr>{}</tr>", header);
+        for row in &self.rows {
+            let cells = row
+                .iter()
+                .enumerate()
+                .map(|(i, cell)| {
+                    let class = match (i < self.label_columns, cell.change) {
+                        (true, _) => "",
+                        (false, Change::Same) => " class=\"number\"",
+                        (false, Change::Faster) => " class=\"number faster\"",
+                        (false, Change::Slower) => " class=\"number slower\"",
+                    };
+                    format!("<td{}>{}</td>", class, escape(&cell.text))
+                })
+                .collect::<String>();
+            println!("<tr>{}</tr>", cells);
+        }
+
+        println!("</table>");
+        println!("</body>");
+        println!("</html>");
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
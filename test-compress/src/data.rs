@@ -0,0 +1,67 @@
+//! Datasets to compress, embedded in native builds and read from files on WebAssembly, where WASI
+//! gives access to the directories the runtime preopens
+
+use std::path::Path;
+
+/// Directory the datasets are read from when they aren't embedded, relative to the current
+/// directory
+const DEFAULT_DIR: &str = "data";
+
+pub struct Dataset {
+    pub name: String,
+    pub bytes: Vec<u8>,
+}
+
+/// The datasets in `dir`, or the embedded ones if there are any
+pub fn datasets(dir: Option<&Path>) -> Vec<Dataset> {
+    match dir {
+        Some(dir) => load(dir),
+        None => embedded().unwrap_or_else(|| load(Path::new(DEFAULT_DIR))),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn embedded() -> Option<Vec<Dataset>> {
+    let dataset = |name: &str, bytes: &[u8]| Dataset {
+        name: name.to_string(),
+        bytes: bytes.to_vec(),
+    };
+    Some(vec![
+        dataset("bincode", include_bytes!("../data/bincode")),
+        dataset("json", include_bytes!("../data/json")),
+        dataset("wasm", include_bytes!("../data/wasm")),
+    ])
+}
+
+/// The datasets would add several MB to the module, which runtimes compile before running it
+#[cfg(target_arch = "wasm32")]
+fn embedded() -> Option<Vec<Dataset>> {
+    None
+}
+
+/// Reads every file in the directory as a dataset named after the file, in name order, exiting
+/// with the error if the directory can't be read
+fn load(dir: &Path) -> Vec<Dataset> {
+    let read = || -> std::io::Result<Vec<Dataset>> {
+        let mut datasets = vec![];
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_file() {
+                datasets.push(Dataset {
+                    name: path.file_name().unwrap().to_string_lossy().into_owned(),
+                    bytes: std::fs::read(&path)?,
+                });
+            }
+        }
+        datasets.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(datasets)
+    };
+
+    match read() {
+        Ok(datasets) => datasets,
+        Err(err) => {
+            eprintln!("failed to read the datasets in {}: {}", dir.display(), err);
+            std::process::exit(1);
+        }
+    }
+}
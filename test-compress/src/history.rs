@@ -0,0 +1,67 @@
+//! Recording of the runs to the history database and the trends of each codec over them, see
+//! `bench_common::history`
+
+use std::path::Path;
+
+use bench_common::history::{parse_window, Build, History, Trends};
+
+/// The build of test-compress, from its build script
+fn build() -> Build {
+    let mut features = vec![];
+    if cfg!(feature = "lz4-flex-safe") {
+        features.push("lz4-flex-safe");
+    }
+    if cfg!(feature = "miniz-simd") {
+        features.push("miniz-simd");
+    }
+    if cfg!(feature = "non_rust") {
+        features.push("non_rust");
+    }
+    Build {
+        tool: env!("CARGO_PKG_NAME"),
+        git_commit: env!("GIT_COMMIT"),
+        rustc: env!("RUSTC_VERSION"),
+        target: env!("TARGET"),
+        crates: include_str!(concat!(env!("OUT_DIR"), "/crates.txt")),
+        features,
+    }
+}
+
+/// Opens the history database if asked for, exiting with the error if it can't be
+pub fn open(path: Option<&Path>, command: &'static str) -> Option<History> {
+    bench_common::history::open(path, build(), command)
+}
+
+#[derive(argh::FromArgs)]
+/// show the speeds and ratio of each codec over the runs recorded with --history, flagging step
+/// changes and what changed in the build between the runs
+#[argh(subcommand, name = "history")]
+pub struct Options {
+    /// SQLite database the runs were recorded to with --history
+    #[argh(positional)]
+    database: std::path::PathBuf,
+
+    /// only show results with this in their name, of the source, codec and dataset
+    #[argh(option)]
+    filter: Option<String>,
+
+    /// changes from the baseline larger than this fraction are flagged as step changes
+    #[argh(option, default = "0.1")]
+    threshold: f64,
+
+    /// number of earlier runs whose median is the baseline each run is compared against
+    #[argh(option, default = "5", from_str_fn(parse_window))]
+    window: usize,
+}
+
+/// Prints each codec result in the database that matches the filter over the runs, oldest first,
+/// flagging step changes with what changed in the build
+pub fn run(options: &Options) {
+    let trends = Trends {
+        tool: env!("CARGO_PKG_NAME"),
+        filter: options.filter.as_deref(),
+        threshold: options.threshold,
+        window: options.window,
+    };
+    bench_common::history::run(&options.database, &trends);
+}
@@ -0,0 +1,120 @@
+//! Self-contained HTML report with SVG charts drawn with plotters: compression ratio against
+//! compress and decompress speed of each codec, per dataset
+
+use bench_common::html::document;
+use plotters::prelude::*;
+use std::path::Path;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+const WIDTH: u32 = 1200;
+const HEIGHT: u32 = 500;
+
+/// A codec on one dataset
+struct Point {
+    name: String,
+    ratio: f64,
+    compress_speed: f64,
+    decompress_speed: f64,
+}
+
+#[derive(Default)]
+pub struct Charts {
+    /// Codec results per dataset
+    datasets: Vec<(String, Vec<Point>)>,
+}
+
+impl Charts {
+    /// Adds the singlethreaded speeds in MB/s of a codec on a dataset
+    pub fn add(
+        &mut self,
+        data: &str,
+        name: String,
+        ratio: f64,
+        compress_speed: f64,
+        decompress_speed: f64,
+    ) {
+        let point = Point {
+            name,
+            ratio,
+            compress_speed,
+            decompress_speed,
+        };
+        match self.datasets.iter_mut().find(|(d, _)| d == data) {
+            Some((_, points)) => points.push(point),
+            None => self.datasets.push((data.to_string(), vec![point])),
+        }
+    }
+
+    /// Writes the report, exiting with the error if it can't be
+    pub fn write(&self, path: &Path) {
+        let result = (|| -> Result<()> {
+            let mut sections = vec![];
+            for (data, points) in &self.datasets {
+                if let Some(plots) = scatter_plots(points)? {
+                    sections.push((format!("data: {}", data), plots));
+                }
+            }
+            std::fs::write(path, document("test-compress", &sections))?;
+            Ok(())
+        })();
+
+        if let Err(err) = result {
+            eprintln!("failed to write {}: {}", path.display(), err);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Ratio against compress speed and against decompress speed side by side, with the speeds on a
+/// logarithmic axis as they span orders of magnitude between codecs, or nothing without points
+fn scatter_plots(points: &[Point]) -> Result<Option<String>> {
+    if points.is_empty() {
+        return Ok(None);
+    }
+
+    let mut svg = String::new();
+    {
+        let root = SVGBackend::with_string(&mut svg, (WIDTH, HEIGHT)).into_drawing_area();
+        root.fill(&WHITE)?;
+        let areas = root.split_evenly((1, 2));
+
+        let max_ratio = points.iter().map(|p| p.ratio).fold(1.0, f64::max);
+        let plots: [(&str, fn(&Point) -> f64); 2] = [
+            ("Compress", |p| p.compress_speed),
+            ("Decompress", |p| p.decompress_speed),
+        ];
+
+        for (area, (title, speed)) in areas.iter().zip(&plots) {
+            let min_speed = points.iter().map(speed).fold(f64::INFINITY, f64::min);
+            let max_speed = points.iter().map(speed).fold(0.0, f64::max);
+
+            let mut chart = ChartBuilder::on(area)
+                .caption(*title, ("sans-serif", 20))
+                .margin(10)
+                .x_label_area_size(40)
+                .y_label_area_size(50)
+                .build_cartesian_2d(
+                    (min_speed * 0.8..max_speed * 1.25).log_scale(),
+                    0.0..max_ratio * 1.1,
+                )?;
+
+            chart
+                .configure_mesh()
+                .x_desc("MB/s")
+                .x_label_formatter(&|speed| format!("{:.0}", speed))
+                .y_desc("ratio")
+                .y_label_formatter(&|ratio| format!("{:.1}x", ratio))
+                .draw()?;
+
+            chart.draw_series(points.iter().map(|p| {
+                EmptyElement::at((speed(p), p.ratio))
+                    + Circle::new((0, 0), 3, BLUE.filled())
+                    + Text::new(p.name.clone(), (5, -5), ("sans-serif", 11).into_font())
+            }))?;
+        }
+
+        root.present()?;
+    }
+    Ok(Some(svg))
+}
@@ -1,11 +1,21 @@
 #![allow(unused_imports, clippy::type_complexity)]
 
+use bench_common::cache::{CacheMode, Evictor};
+use bench_common::counters::{self, CounterResult, Counters, Counts};
+use bench_common::profile;
 use rayon::prelude::*;
+use serde::Serialize;
 use std::{
     io::{Cursor, Read},
     time::{Duration, Instant},
 };
 
+mod data;
+mod history;
+mod html;
+mod report;
+mod simd;
+
 struct Codec {
     pub source: &'static str,
     pub name: &'static str,
@@ -125,7 +135,7 @@ fn codecs() -> Vec<Codec> {
         },
     ]);
 
-    #[cfg(feature = "non_rust")]
+    #[cfg(all(feature = "non_rust", not(target_arch = "wasm32")))]
     for (comp_name, compression) in [
         ("bzip2-fast", bzip2::Compression::fast()),
         ("bzip2-best", bzip2::Compression::best()),
@@ -151,7 +161,7 @@ fn codecs() -> Vec<Codec> {
         });
     }
 
-    #[cfg(feature = "non_rust")]
+    #[cfg(all(feature = "non_rust", not(target_arch = "wasm32")))]
     for (name, level) in [
         // default as same as 0 here
         //("zstd-def", zstd::DEFAULT_COMPRESSION_LEVEL),
@@ -187,14 +197,22 @@ fn codecs() -> Vec<Codec> {
     v
 }
 
-struct CodecTestOutput {
-    codec: Codec,
-    compress_size: usize,
-
+/// Durations of one codec in one cache mode
+struct Timings {
     st_compress_duration: Duration,
     st_decompress_duration: Duration,
     mt_compress_duration: Option<Duration>,
     mt_decompress_duration: Option<Duration>,
+    st_compress_counts: Option<Counts>,
+    st_decompress_counts: Option<Counts>,
+}
+
+struct CodecTestOutput {
+    codec: Codec,
+    compress_size: usize,
+
+    /// Timings in each of the cache modes, in the order they were given
+    timings: Vec<Timings>,
 }
 
 #[derive(argh::FromArgs)]
@@ -203,69 +221,231 @@ struct Options {
     /// run parallel compression/decompression tests
     #[argh(switch, short = 'p')]
     parallel: bool,
+
+    /// cache state before each timed run: warm (default) or cold, where the last-level cache is
+    /// evicted first. Repeat to report both side by side
+    #[argh(option)]
+    cache: Vec<CacheMode>,
+
+    /// record cycles, instructions, cache misses and branch misses of the singlethreaded runs
+    /// with Linux perf_event_open hardware counters
+    #[argh(switch)]
+    counters: bool,
+
+    /// directory to write a flamegraph SVG and a pprof protobuf of each codec and dataset to,
+    /// sampled while repeating compression and decompression after the timed runs
+    #[argh(option)]
+    profile: Option<std::path::PathBuf>,
+
+    /// directory to read the datasets from, one per file, instead of the embedded ones. Builds for
+    /// wasm32 don't embed them and read the data directory of the current directory by default
+    #[argh(option)]
+    data: Option<std::path::PathBuf>,
+
+    /// write an HTML report with charts of compression ratio against singlethreaded compress and
+    /// decompress speed of each dataset to this file, in the first cache mode
+    #[argh(option)]
+    html: Option<std::path::PathBuf>,
+
+    /// print the results as CSV, one row per dataset, codec and cache mode
+    #[argh(switch)]
+    csv: bool,
+
+    /// print the results as JSON with metadata about the run, one result per dataset, codec and
+    /// cache mode
+    #[argh(switch)]
+    json: bool,
+
+    /// append the run, with its git commit, rustc version, crate versions, CPU model, target and
+    /// features, to this SQLite database, see the history subcommand
+    #[argh(option)]
+    history: Option<std::path::PathBuf>,
+
+    #[argh(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(argh::FromArgs)]
+#[argh(subcommand)]
+enum Command {
+    History(history::Options),
+}
+
+#[derive(Serialize)]
+struct CodecResult<'a> {
+    data: &'a str,
+    source: &'a str,
+    codec: &'a str,
+    backend: &'a str,
+    bytes: usize,
+    compressed_bytes: usize,
+    ratio: f32,
+    cache: String,
+    compress_mb_per_s: f64,
+    decompress_mb_per_s: f64,
+    mt_compress_mb_per_s: Option<f64>,
+    mt_decompress_mb_per_s: Option<f64>,
+    compress_counters: Option<CounterResult>,
+    decompress_counters: Option<CounterResult>,
+}
+
+fn test_codec(
+    codec: &Codec,
+    data_bytes: &[u8],
+    threads: usize,
+    parallel: bool,
+    mode: CacheMode,
+    evictor: &mut Evictor,
+    counters: &mut Option<Counters>,
+) -> (usize, Timings) {
+    // singlethreaded test
+
+    evictor.prepare(mode);
+    let start_time = Instant::now();
+    let (compress_bytes, st_compress_counts) =
+        counters::measure(counters, || (codec.compress_fn)(data_bytes));
+    let st_compress_duration = start_time.elapsed();
+
+    evictor.prepare(mode);
+    let start_time2 = Instant::now();
+    let (decompress_bytes, st_decompress_counts) =
+        counters::measure(counters, || (codec.decompress_fn)(&compress_bytes));
+    let st_decompress_duration = start_time2.elapsed();
+
+    assert_eq!(data_bytes, &decompress_bytes);
+
+    let (mt_compress_duration, mt_decompress_duration) = if parallel {
+        // multithreaded test
+        evictor.prepare(mode);
+        let start_time = Instant::now();
+        (0..threads).into_par_iter().for_each(|_i| {
+            let _ = (codec.compress_fn)(data_bytes);
+        });
+        let mt_compress_duration = start_time.elapsed() / threads as u32;
+
+        evictor.prepare(mode);
+        let start_time = Instant::now();
+        (0..threads).into_par_iter().for_each(|_i| {
+            let _ = (codec.decompress_fn)(&compress_bytes);
+        });
+        let mt_decompress_duration = start_time.elapsed() / threads as u32;
+        (Some(mt_compress_duration), Some(mt_decompress_duration))
+    } else {
+        (None, None)
+    };
+
+    (
+        compress_bytes.len(),
+        Timings {
+            st_compress_duration,
+            st_decompress_duration,
+            mt_compress_duration,
+            mt_decompress_duration,
+            st_compress_counts,
+            st_decompress_counts,
+        },
+    )
 }
 
 fn main() {
-    let options: Options = argh::from_env();
+    let mut options: Options = argh::from_env();
+    if let Some(Command::History(history_options)) = &options.command {
+        history::run(history_options);
+        return;
+    }
+
+    if options.cache.is_empty() {
+        options.cache.push(CacheMode::Warm);
+    }
+    // label the speeds with their cache mode, unless only the default warm mode is run
+    let show_mode = options.cache != [CacheMode::Warm];
+    let mut evictor = Evictor::new(&options.cache);
+    let mut counters = counters::open(options.counters);
+    let profiler = profile::open(options.profile.as_deref());
+    let mut charts = options.html.as_ref().map(|_| html::Charts::default());
+    let mut history = history::open(options.history.as_deref(), "compress");
 
-    let datas = vec![
-        ("bincode", include_bytes!("../data/bincode").to_vec()),
-        ("json", include_bytes!("../data/json").to_vec()),
-        ("wasm", include_bytes!("../data/wasm").to_vec()),
-    ];
+    let datasets = data::datasets(options.data.as_deref());
 
+    // WebAssembly runs singlethreaded, WASI has no threads to build a thread pool with
+    if cfg!(target_arch = "wasm32") && options.parallel {
+        eprintln!("parallel tests aren't supported on wasm32");
+        std::process::exit(1);
+    }
+    if options.csv && options.json {
+        eprintln!("--csv and --json can't be combined");
+        std::process::exit(1);
+    }
     let threads = num_cpus::get();
+    #[cfg(not(target_arch = "wasm32"))]
     rayon::ThreadPoolBuilder::new()
         .num_threads(threads)
         .build_global()
         .unwrap();
 
-    for (data_name, data_bytes) in &datas {
-        println!(
-            "----- data: {:7} ----------------------------------------",
-            data_name
-        );
+    if options.csv {
+        print!("data,source,codec,backend,ratio,cache,compress MB/s,decompress MB/s");
+        if counters.is_some() {
+            print!(",compress cycles/B,decompress cycles/B");
+        }
+        println!();
+    } else if !options.json {
+        println!("CPU features: {}", simd::cpu_features().join(" "));
+    }
+
+    // datasets are embedded in native builds unless a directory is given
+    let input = if options.data.is_none() && !cfg!(target_arch = "wasm32") {
+        "embedded"
+    } else {
+        "files"
+    };
+    let mut report = if options.json {
+        Some(report::Report::new(report::Metadata::new(threads, input)))
+    } else {
+        None
+    };
+
+    for dataset in &datasets {
+        let (data_name, data_bytes) = (&dataset.name, &dataset.bytes);
+        if !options.csv && !options.json {
+            println!(
+                "----- data: {:7} ----------------------------------------",
+                data_name
+            );
+        }
 
         let mut results = codecs()
             .into_iter()
             .map(|codec| {
-                // singlethreaded test
-
-                let start_time = Instant::now();
-                let compress_bytes = (codec.compress_fn)(data_bytes);
-                let st_compress_duration = start_time.elapsed();
+                let mut compress_size = 0;
+                let timings = options
+                    .cache
+                    .iter()
+                    .map(|mode| {
+                        let (size, timings) = test_codec(
+                            &codec,
+                            data_bytes,
+                            threads,
+                            options.parallel,
+                            *mode,
+                            &mut evictor,
+                            &mut counters,
+                        );
+                        compress_size = size;
+                        timings
+                    })
+                    .collect();
 
-                let start_time2 = Instant::now();
-                let decompress_bytes = (codec.decompress_fn)(&compress_bytes);
-                let st_decompress_duration = start_time2.elapsed();
-
-                assert_eq!(data_bytes, &decompress_bytes);
-
-                let (mt_compress_duration, mt_decompress_duration) = if options.parallel {
-                    // multithreaded test
-                    let start_time = Instant::now();
-                    (0..threads).into_par_iter().for_each(|_i| {
-                        let _ = (codec.compress_fn)(data_bytes);
-                    });
-                    let mt_compress_duration = start_time.elapsed() / threads as u32;
-
-                    let start_time = Instant::now();
-                    (0..threads).into_par_iter().for_each(|_i| {
-                        let _ = (codec.decompress_fn)(&compress_bytes);
-                    });
-                    let mt_decompress_duration = start_time.elapsed() / threads as u32;
-                    (Some(mt_compress_duration), Some(mt_decompress_duration))
-                } else {
-                    (None, None)
-                };
+                let name = format!("{}-{}-{}", codec.source, codec.name, data_name);
+                profile::profile(&profiler, &name, || {
+                    let compress_bytes = (codec.compress_fn)(data_bytes);
+                    let _ = (codec.decompress_fn)(&compress_bytes);
+                });
 
                 CodecTestOutput {
                     codec,
-                    compress_size: compress_bytes.len(),
-                    st_compress_duration,
-                    st_decompress_duration,
-                    mt_compress_duration,
-                    mt_decompress_duration,
+                    compress_size,
+                    timings,
                 }
             })
             .collect::<Vec<_>>();
@@ -275,33 +455,143 @@ fn main() {
         for r in results {
             let source = r.codec.source;
             let name = r.codec.name;
+            let backend = simd::backend(source);
             let compression_ratio = data_bytes.len() as f32 / r.compress_size as f32;
-            let st_compress_speed = (data_bytes.len() as f64)
-                / (1024f64 * 1024f64)
-                / r.st_compress_duration.as_secs_f64();
-            let st_decompress_speed = (data_bytes.len() as f64)
-                / (1024f64 * 1024f64)
-                / r.st_decompress_duration.as_secs_f64();
-
-            if let (Some(mt_compress_duration), Some(mt_decompress_duration)) =
-                (r.mt_compress_duration, r.mt_decompress_duration)
-            {
-                let mt_compress_speed = (data_bytes.len() as f64)
-                    / (1024f64 * 1024f64)
-                    / mt_compress_duration.as_secs_f64();
-                let mt_compress_ratio =
-                    r.st_compress_duration.as_secs_f64() / mt_compress_duration.as_secs_f64();
 
-                let mt_decompress_speed = (data_bytes.len() as f64)
+            if let Some(charts) = &mut charts {
+                let t = &r.timings[0];
+                let mb = data_bytes.len() as f64 / (1024f64 * 1024f64);
+                charts.add(
+                    data_name,
+                    format!("{} {}", source, name),
+                    compression_ratio as f64,
+                    mb / t.st_compress_duration.as_secs_f64(),
+                    mb / t.st_decompress_duration.as_secs_f64(),
+                );
+            }
+
+            if let Some(history) = &mut history {
+                let name = format!("{} {} {}", source, name, data_name);
+                for (mode, t) in options.cache.iter().zip(&r.timings) {
+                    let mb = data_bytes.len() as f64 / (1024f64 * 1024f64);
+                    let mode = mode.to_string();
+                    let compress_speed = mb / t.st_compress_duration.as_secs_f64();
+                    let decompress_speed = mb / t.st_decompress_duration.as_secs_f64();
+                    history.add(name.clone(), &mode, "compress MB/s", compress_speed);
+                    history.add(name.clone(), &mode, "decompress MB/s", decompress_speed);
+                    history.add(name.clone(), &mode, "ratio", compression_ratio as f64);
+                }
+            }
+
+            if options.csv {
+                for (mode, t) in options.cache.iter().zip(&r.timings) {
+                    let st_compress_speed = (data_bytes.len() as f64)
+                        / (1024f64 * 1024f64)
+                        / t.st_compress_duration.as_secs_f64();
+                    let st_decompress_speed = (data_bytes.len() as f64)
+                        / (1024f64 * 1024f64)
+                        / t.st_decompress_duration.as_secs_f64();
+                    print!("{data_name},{source},{name},{backend},{compression_ratio:.3},{mode},{st_compress_speed:.2},{st_decompress_speed:.2}");
+                    if let (Some(c), Some(d)) = (t.st_compress_counts, t.st_decompress_counts) {
+                        let (c, d) = (c.per_byte(data_bytes.len()), d.per_byte(data_bytes.len()));
+                        print!(",{:.3},{:.3}", c.cycles_per_byte, d.cycles_per_byte);
+                    }
+                    println!();
+                }
+                continue;
+            }
+
+            if let Some(report) = &mut report {
+                let mb_per_s = |duration: Duration| {
+                    data_bytes.len() as f64 / (1024f64 * 1024f64) / duration.as_secs_f64()
+                };
+                for (mode, t) in options.cache.iter().zip(&r.timings) {
+                    report.add(CodecResult {
+                        data: data_name,
+                        source,
+                        codec: name,
+                        backend,
+                        bytes: data_bytes.len(),
+                        compressed_bytes: r.compress_size,
+                        ratio: compression_ratio,
+                        cache: mode.to_string(),
+                        compress_mb_per_s: mb_per_s(t.st_compress_duration),
+                        decompress_mb_per_s: mb_per_s(t.st_decompress_duration),
+                        mt_compress_mb_per_s: t.mt_compress_duration.map(mb_per_s),
+                        mt_decompress_mb_per_s: t.mt_decompress_duration.map(mb_per_s),
+                        compress_counters: t
+                            .st_compress_counts
+                            .map(|c| c.per_byte(data_bytes.len())),
+                        decompress_counters: t
+                            .st_decompress_counts
+                            .map(|c| c.per_byte(data_bytes.len())),
+                    });
+                }
+                continue;
+            }
+
+            print!("{source:20} {name:12} {backend:12} {compression_ratio:.2}x");
+
+            for (mode, t) in options.cache.iter().zip(&r.timings) {
+                if show_mode {
+                    print!(" {mode}:");
+                }
+
+                let st_compress_speed = (data_bytes.len() as f64)
+                    / (1024f64 * 1024f64)
+                    / t.st_compress_duration.as_secs_f64();
+                let st_decompress_speed = (data_bytes.len() as f64)
                     / (1024f64 * 1024f64)
-                    / mt_decompress_duration.as_secs_f64();
-                let mt_decompress_ratio =
-                    r.st_decompress_duration.as_secs_f64() / mt_decompress_duration.as_secs_f64();
+                    / t.st_decompress_duration.as_secs_f64();
 
-                println!("{source:20} {name:12} {compression_ratio:.2}x {st_compress_speed:>5.0} MB/s {mt_compress_speed:>5.0} MB/s, {mt_compress_ratio:>4.1}x  {st_decompress_speed:>5.0} MB/s {mt_decompress_speed:>5.0} MB/s, {mt_decompress_ratio:>4.1}x");
-            } else {
-                println!("{source:20} {name:12} {compression_ratio:.2}x {st_compress_speed:>5.0} MB/s {st_decompress_speed:>5.0} MB/s");
+                if let (Some(mt_compress_duration), Some(mt_decompress_duration)) =
+                    (t.mt_compress_duration, t.mt_decompress_duration)
+                {
+                    let mt_compress_speed = (data_bytes.len() as f64)
+                        / (1024f64 * 1024f64)
+                        / mt_compress_duration.as_secs_f64();
+                    let mt_compress_ratio =
+                        t.st_compress_duration.as_secs_f64() / mt_compress_duration.as_secs_f64();
+
+                    let mt_decompress_speed = (data_bytes.len() as f64)
+                        / (1024f64 * 1024f64)
+                        / mt_decompress_duration.as_secs_f64();
+                    let mt_decompress_ratio = t.st_decompress_duration.as_secs_f64()
+                        / mt_decompress_duration.as_secs_f64();
+
+                    print!(" {st_compress_speed:>5.0} MB/s {mt_compress_speed:>5.0} MB/s, {mt_compress_ratio:>4.1}x  {st_decompress_speed:>5.0} MB/s {mt_decompress_speed:>5.0} MB/s, {mt_decompress_ratio:>4.1}x");
+                } else {
+                    print!(" {st_compress_speed:>5.0} MB/s {st_decompress_speed:>5.0} MB/s");
+                }
+
+                if let (Some(c), Some(d)) = (t.st_compress_counts, t.st_decompress_counts) {
+                    let (c, d) = (c.per_byte(data_bytes.len()), d.per_byte(data_bytes.len()));
+                    print!(
+                        " ({:.2} / {:.2} cycles/B, {:.2} / {:.2} instr/B, {:.2} / {:.2} IPC, {} / {} cache misses, {} / {} branch misses)",
+                        c.cycles_per_byte,
+                        d.cycles_per_byte,
+                        c.instructions_per_byte,
+                        d.instructions_per_byte,
+                        c.ipc,
+                        d.ipc,
+                        c.cache_misses,
+                        d.cache_misses,
+                        c.branch_misses,
+                        d.branch_misses
+                    );
+                }
             }
+            println!();
         }
     }
+
+    if let Some(report) = report {
+        report.finish();
+    }
+    if let (Some(charts), Some(path)) = (&charts, &options.html) {
+        charts.write(path);
+    }
+    if let Some(history) = history {
+        history.write();
+    }
 }
@@ -0,0 +1,70 @@
+//! JSON output, with the same metadata about the run as test-hash's structured output formats
+
+use serde::Serialize;
+use serde_json::Value;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Describes the run so results from different platforms and runtimes can be merged
+#[derive(Serialize)]
+pub struct Metadata {
+    pub tool: &'static str,
+    pub version: &'static str,
+    pub command: &'static str,
+    pub target: &'static str,
+    pub os: &'static str,
+    pub arch: &'static str,
+    pub cpu_features: Vec<&'static str>,
+    pub threads: usize,
+    /// Datasets differ in size, each result has its own
+    pub size_bytes: Option<usize>,
+    pub input: &'static str,
+    pub timestamp: u64,
+}
+
+impl Metadata {
+    pub fn new(threads: usize, input: &'static str) -> Self {
+        Self {
+            tool: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+            command: "compress",
+            target: env!("TARGET"),
+            os: std::env::consts::OS,
+            arch: std::env::consts::ARCH,
+            cpu_features: crate::simd::cpu_features(),
+            threads,
+            size_bytes: None,
+            input,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        }
+    }
+}
+
+/// Collects results to print them with the metadata once the run is done
+pub struct Report {
+    metadata: Metadata,
+    results: Vec<Value>,
+}
+
+impl Report {
+    pub fn new(metadata: Metadata) -> Self {
+        Self {
+            metadata,
+            results: vec![],
+        }
+    }
+
+    pub fn add(&mut self, result: impl Serialize) {
+        self.results.push(serde_json::to_value(result).unwrap());
+    }
+
+    pub fn finish(self) {
+        let report = serde_json::json!({
+            "metadata": self.metadata,
+            "results": self.results,
+        });
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    }
+}
@@ -0,0 +1,19 @@
+//! Detected CPU features and the backend each codec runs with
+
+pub use bench_common::simd::cpu_features;
+
+/// The backend a codec source runs with, or `-` where it isn't known
+pub fn backend(source: &str) -> &'static str {
+    match source {
+        // lz4_flex is built without its default safe-encode and safe-decode features, unless
+        // lz4-flex-safe is enabled
+        "lz4-flex" if cfg!(feature = "lz4-flex-safe") => "safe",
+        "lz4-flex" => "unsafe",
+        // miniz_oxide only checksums with simd-adler32 when built with its simd feature
+        "miniz_oxide" if cfg!(feature = "miniz-simd") => "simd-adler32",
+        "miniz_oxide" | "lz4-compression" | "snap" => "scalar",
+        // the Cloudflare fork of zlib requires SSE 4.2 and PCLMULQDQ
+        "cloudflare-zlib" => "sse4.2",
+        _ => "-",
+    }
+}
@@ -0,0 +1,246 @@
+//! Throughput of each hash over slices starting at every offset within a cache line, from heap,
+//! page-aligned or huge-page backed buffers
+
+use std::alloc::{self, Layout};
+use std::time::Instant;
+use structopt::{clap::arg_enum, StructOpt};
+
+use crate::registry::HashEntry;
+use crate::report::{Metadata, Report};
+use crate::Format;
+
+const PAGE_SIZE: usize = 4096;
+const HUGE_PAGE_SIZE: usize = 2 * 1024 * 1024;
+
+arg_enum! {
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Placement {
+    Heap,
+    Page,
+    Huge,
+}
+}
+
+#[derive(StructOpt)]
+pub struct Options {
+    /// Hash slices starting at every offset from 0 up to and including this
+    #[structopt(long, default_value = "63")]
+    max_offset: usize,
+
+    /// Buffer to hash from: a heap `Vec` with allocator-chosen alignment, a page-aligned
+    /// allocation, or a 2 MiB aligned allocation advised to use transparent huge pages
+    #[structopt(long, default_value = "Heap", possible_values = &Placement::variants(), case_insensitive = true)]
+    buffer: Placement,
+
+    /// Runs per offset, the fastest is reported
+    #[structopt(long, default_value = "3")]
+    runs: usize,
+}
+
+/// Zero-filled buffer with a fixed alignment, or from the heap allocator for `Placement::Heap`
+enum Buffer {
+    Heap(Vec<u8>),
+    Aligned { ptr: *mut u8, layout: Layout },
+}
+
+impl Buffer {
+    fn new(placement: Placement, len: usize) -> Self {
+        let align = match placement {
+            Placement::Heap => {
+                let mut bytes = vec![0u8; len];
+                fill(bytes.as_mut_ptr(), len);
+                return Buffer::Heap(bytes);
+            }
+            Placement::Page => PAGE_SIZE,
+            Placement::Huge => HUGE_PAGE_SIZE,
+        };
+
+        // alignments are powers of two
+        let size = (len + align - 1) & !(align - 1);
+        let layout = Layout::from_size_align(size, align).unwrap();
+        // allocating a zero-size layout is undefined behavior, `--size` is at least 1 MB
+        debug_assert!(layout.size() != 0);
+        // SAFETY: the layout has a non-zero size, and the memory is written before it's read
+        let ptr = unsafe { alloc::alloc(layout) };
+        if ptr.is_null() {
+            alloc::handle_alloc_error(layout);
+        }
+
+        if placement == Placement::Huge && !advise_huge_pages(ptr, size) {
+            eprintln!("transparent huge pages not available, the buffer is only 2 MiB aligned");
+        }
+
+        // fill after the advice, so the pages are faulted in as huge pages
+        fill(ptr, size);
+
+        Buffer::Aligned { ptr, layout }
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        match self {
+            Buffer::Heap(bytes) => bytes,
+            Buffer::Aligned { ptr, layout } => unsafe {
+                std::slice::from_raw_parts(*ptr, layout.size())
+            },
+        }
+    }
+}
+
+/// Writes zeros over the buffer so its pages are faulted in rather than left as the shared zero
+/// page, which every placement gets before it is hashed. The pointer is passed through
+/// `black_box` so the write isn't folded into a zeroed allocation
+fn fill(ptr: *mut u8, size: usize) {
+    unsafe { std::ptr::write_bytes(std::hint::black_box(ptr), 0, size) };
+}
+
+impl Drop for Buffer {
+    fn drop(&mut self) {
+        if let Buffer::Aligned { ptr, layout } = *self {
+            unsafe { alloc::dealloc(ptr, layout) };
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn advise_huge_pages(ptr: *mut u8, size: usize) -> bool {
+    unsafe { libc::madvise(ptr as *mut libc::c_void, size, libc::MADV_HUGEPAGE) == 0 }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn advise_huge_pages(_ptr: *mut u8, _size: usize) -> bool {
+    false
+}
+
+#[derive(serde::Serialize)]
+struct AlignmentResult<'a> {
+    implementation: &'a str,
+    hash: &'a str,
+    buffer: String,
+    offset: usize,
+    mb_per_s: f64,
+    /// Throughput relative to offset 0
+    relative: f64,
+}
+
+/// Fastest throughput of `runs` hashes of the slice, checking they all give the same digest
+fn measure(entry: &HashEntry, bytes: &[u8], runs: usize, expected: &[u8]) -> f64 {
+    (0..runs.max(1))
+        .map(|_| {
+            let start_time = Instant::now();
+            let hash_result = (entry.func)(bytes);
+            let duration = start_time.elapsed().as_secs_f64();
+            assert_eq!(
+                hash_result, expected,
+                "{} {} gives a different digest for a misaligned slice",
+                entry.implementation, entry.name
+            );
+            (bytes.len() as f64) / (1024f64 * 1024f64) / duration
+        })
+        .fold(0f64, f64::max)
+}
+
+pub fn run(
+    options: &Options,
+    format: Format,
+    filter: Option<&str>,
+    size: usize,
+    mut hashes: Vec<HashEntry>,
+) {
+    hashes.sort_by(|hash1, hash2| {
+        hash1
+            .name
+            .to_ascii_lowercase()
+            .cmp(&hash2.name.to_ascii_lowercase())
+    });
+
+    let len = size * 1024 * 1024;
+    let buffer = Buffer::new(options.buffer, len + options.max_offset);
+    let bytes = buffer.as_slice();
+    let placement = options.buffer.to_string().to_ascii_lowercase();
+
+    let metadata = Metadata::new("alignment", 1, Some(len), "zeros");
+    let mut report = Report::new(format, metadata);
+
+    if format == Format::Csv {
+        println!("implementation,hash,buffer,offset,MB/s,relative");
+    }
+
+    for entry in &hashes {
+        if let Some(filter) = filter {
+            if !entry.implementation.contains(filter) {
+                continue; // skip
+            }
+        }
+
+        // the digest is the same at every offset, as the buffer is all zeros
+        let expected = (entry.func)(&bytes[..len]);
+        let speeds = (0..=options.max_offset)
+            .map(|offset| measure(entry, &bytes[offset..offset + len], options.runs, &expected))
+            .collect::<Vec<_>>();
+
+        match format {
+            Format::Text => {
+                let (min_offset, min) = extreme(&speeds, |a, b| a < b);
+                let (max_offset, max) = extreme(&speeds, |a, b| a > b);
+                println!(
+                    "{:20} {:20} min {:>6.0} MB/s at +{:<2} max {:>6.0} MB/s at +{:<2} spread {:>4.2}x",
+                    entry.name,
+                    entry.implementation,
+                    min,
+                    min_offset,
+                    max,
+                    max_offset,
+                    max / min
+                );
+                for (row, chunk) in speeds.chunks(8).enumerate() {
+                    print!("  +{:<2}", row * 8);
+                    for speed in chunk {
+                        print!(" {:>6.0}", speed);
+                    }
+                    println!();
+                }
+            }
+            Format::Csv => {
+                for (offset, speed) in speeds.iter().enumerate() {
+                    println!(
+                        "{},{},{},{},{:.0},{:.3}",
+                        entry.implementation,
+                        entry.name,
+                        placement,
+                        offset,
+                        speed,
+                        speed / speeds[0]
+                    );
+                }
+            }
+            Format::Json | Format::Markdown => {
+                for (offset, speed) in speeds.iter().enumerate() {
+                    report.add(AlignmentResult {
+                        implementation: entry.implementation,
+                        hash: entry.name,
+                        buffer: placement.clone(),
+                        offset,
+                        mb_per_s: *speed,
+                        relative: speed / speeds[0],
+                    });
+                }
+            }
+        }
+    }
+
+    report.finish();
+}
+
+/// Offset and speed of the slowest or fastest offset, depending on `better`
+fn extreme(speeds: &[f64], better: impl Fn(f64, f64) -> bool) -> (usize, f64) {
+    speeds.iter().copied().enumerate().fold(
+        (0, speeds[0]),
+        |(best_offset, best), (offset, speed)| {
+            if better(speed, best) {
+                (offset, speed)
+            } else {
+                (best_offset, best)
+            }
+        },
+    )
+}
@@ -0,0 +1,224 @@
+//! Bao verified streaming: encoding, slice extraction, slice verification and decoding
+
+use std::io::{Cursor, Read};
+use std::time::Instant;
+
+use crate::report::{Metadata, Report};
+use crate::Format;
+
+/// Content lengths of the slices to extract, from a single chunk up to 1 MiB
+const SLICE_LENGTHS: &[u64] = &[1024, 64 * 1024, 1024 * 1024];
+
+#[derive(serde::Serialize)]
+struct BaoResult<'a> {
+    operation: &'a str,
+    offset: u64,
+    length: u64,
+    us: f64,
+    mb_per_s: f64,
+}
+
+fn print_result(
+    report: &mut Report,
+    format: Format,
+    operation: &str,
+    offset: u64,
+    len: u64,
+    start_time: Instant,
+) {
+    let duration = start_time.elapsed().as_secs_f64();
+    let speed = (len as f64) / (1024f64 * 1024f64) / duration;
+
+    match format {
+        Format::Text => {
+            println!(
+                "{:18} offset {:>10} len {:>10} {:>9.1} us {:>6.0} MB/s",
+                operation,
+                offset,
+                len,
+                duration * 1_000_000f64,
+                speed
+            );
+        }
+        Format::Csv => {
+            println!(
+                "{},{},{},{:.1},{:.0}",
+                operation,
+                offset,
+                len,
+                duration * 1_000_000f64,
+                speed
+            );
+        }
+        Format::Json | Format::Markdown => report.add(BaoResult {
+            operation,
+            offset,
+            length: len,
+            us: duration * 1_000_000f64,
+            mb_per_s: speed,
+        }),
+    }
+}
+
+fn extract_slice(encoded: &[u8], offset: u64, len: u64) -> Vec<u8> {
+    let mut slice = vec![];
+    bao::encode::SliceExtractor::new(Cursor::new(encoded), offset, len)
+        .read_to_end(&mut slice)
+        .unwrap();
+    slice
+}
+
+fn extract_outboard_slice(bytes: &[u8], outboard: &[u8], offset: u64, len: u64) -> Vec<u8> {
+    let mut slice = vec![];
+    bao::encode::SliceExtractor::new_outboard(
+        Cursor::new(bytes),
+        Cursor::new(outboard),
+        offset,
+        len,
+    )
+    .read_to_end(&mut slice)
+    .unwrap();
+    slice
+}
+
+fn decode_slice(slice: &[u8], hash: &bao::Hash, offset: u64, len: u64) -> std::io::Result<Vec<u8>> {
+    let mut content = vec![];
+    bao::decode::SliceDecoder::new(slice, hash, offset, len).read_to_end(&mut content)?;
+    Ok(content)
+}
+
+fn flip_byte(bytes: &[u8], index: usize) -> Vec<u8> {
+    let mut tampered = bytes.to_vec();
+    tampered[index] ^= 1;
+    tampered
+}
+
+/// Checks that tampered encodings, slices and hashes are all rejected
+fn corruption_tests(bytes: &[u8], encoded: &[u8], hash: &bao::Hash) {
+    assert!(
+        bao::decode::decode(flip_byte(encoded, encoded.len() / 2), hash).is_err(),
+        "tampered combined encoding was accepted"
+    );
+
+    let wrong_hash = blake3::hash(b"wrong");
+    assert!(
+        bao::decode::decode(encoded, &wrong_hash).is_err(),
+        "combined encoding was accepted with the wrong hash"
+    );
+
+    for &len in SLICE_LENGTHS {
+        let offset = (bytes.len() as u64 / 2).saturating_sub(len / 2);
+        let slice = extract_slice(encoded, offset, len);
+
+        // the first bytes after the length header are parent nodes, the last are chunk content
+        for &index in &[8, slice.len() / 2, slice.len() - 1] {
+            assert!(
+                decode_slice(&flip_byte(&slice, index), hash, offset, len).is_err(),
+                "tampered slice at offset {} len {} was accepted (byte {} flipped)",
+                offset,
+                len,
+                index
+            );
+        }
+
+        assert!(
+            decode_slice(&slice, &wrong_hash, offset, len).is_err(),
+            "slice at offset {} len {} was accepted with the wrong hash",
+            offset,
+            len
+        );
+    }
+}
+
+pub fn run(format: Format, size: usize) {
+    let bytes = (0..size * 1024 * 1024)
+        .map(|i| (i % 251) as u8)
+        .collect::<Vec<_>>();
+    let size = bytes.len() as u64;
+
+    let mut report = Report::new(
+        format,
+        Metadata::new("bao", 1, Some(bytes.len()), "pattern"),
+    );
+
+    if format == Format::Csv {
+        println!("operation,offset,length,us,MB/s");
+    }
+
+    // encoding
+
+    let start_time = Instant::now();
+    let (encoded, hash) = bao::encode::encode(&bytes);
+    print_result(&mut report, format, "encode-combined", 0, size, start_time);
+
+    let start_time = Instant::now();
+    let (outboard, outboard_hash) = bao::encode::outboard(&bytes);
+    print_result(&mut report, format, "encode-outboard", 0, size, start_time);
+
+    assert_eq!(hash, outboard_hash);
+    assert_eq!(hash, blake3::hash(&bytes));
+
+    // slices at the start, middle and end of the input
+
+    for &len in SLICE_LENGTHS {
+        let len = len.min(size);
+
+        for &offset in &[0, size / 2 - len / 2, size - len] {
+            let start_time = Instant::now();
+            let slice = extract_slice(&encoded, offset, len);
+            print_result(
+                &mut report,
+                format,
+                "extract-combined",
+                offset,
+                len,
+                start_time,
+            );
+
+            let start_time = Instant::now();
+            let outboard_slice = extract_outboard_slice(&bytes, &outboard, offset, len);
+            print_result(
+                &mut report,
+                format,
+                "extract-outboard",
+                offset,
+                len,
+                start_time,
+            );
+
+            assert_eq!(slice, outboard_slice);
+
+            let start_time = Instant::now();
+            let content = decode_slice(&slice, &hash, offset, len).unwrap();
+            print_result(&mut report, format, "verify-slice", offset, len, start_time);
+
+            assert_eq!(
+                &content[..],
+                &bytes[offset as usize..(offset + len) as usize]
+            );
+        }
+    }
+
+    // full verified decoding
+
+    let start_time = Instant::now();
+    let decoded = bao::decode::decode(&encoded, &hash).unwrap();
+    print_result(&mut report, format, "decode-combined", 0, size, start_time);
+    assert_eq!(decoded, bytes);
+
+    let start_time = Instant::now();
+    let mut decoded = vec![];
+    bao::decode::Decoder::new_outboard(Cursor::new(&bytes), Cursor::new(&outboard), &hash)
+        .read_to_end(&mut decoded)
+        .unwrap();
+    print_result(&mut report, format, "decode-outboard", 0, size, start_time);
+    assert_eq!(decoded, bytes);
+
+    corruption_tests(&bytes, &encoded, &hash);
+
+    report.finish();
+
+    if format == Format::Text {
+        println!("tampered encodings, slices and hashes were all rejected");
+    }
+}
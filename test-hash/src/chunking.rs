@@ -0,0 +1,581 @@
+//! Content-defined chunking with rolling hashes: throughput, chunk-size distribution and dedup
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use structopt::StructOpt;
+
+use crate::report::{Metadata, Report};
+use crate::Format;
+
+/// Bytes in the sliding window of the Rabin and Buzhash rolling hashes
+const WINDOW_SIZE: usize = 48;
+
+/// Irreducible polynomial of degree 53 for the Rabin fingerprint
+const RABIN_POLYNOMIAL: u64 = 0x3DA3358B4DC173;
+
+#[derive(StructOpt)]
+pub struct Options {
+    /// Target average chunk sizes in KiB, the minimum and maximum sizes are a quarter and four times that
+    #[structopt(
+        long,
+        require_delimiter = true,
+        default_value = "4,16,64",
+        parse(try_from_str = parse_avg_kib)
+    )]
+    avg_kib: Vec<usize>,
+
+    /// Earlier version of the input, to report how much of the input deduplicates against it
+    #[structopt(long, parse(from_os_str))]
+    base: Option<PathBuf>,
+
+    /// File to chunk
+    #[structopt(parse(from_os_str))]
+    input: PathBuf,
+}
+
+/// Parses a non-zero average chunk size in KiB, small enough that four times it is still a
+/// maximum chunk size FastCDC accepts, which keeps the derived minimum and average within its
+/// limits too
+fn parse_avg_kib(s: &str) -> Result<usize, String> {
+    let avg_kib = s.parse::<usize>().map_err(|err| err.to_string())?;
+    let max_kib = fastcdc::v2020::MAXIMUM_MAX as usize / 4 / 1024;
+    if avg_kib == 0 || avg_kib > max_kib {
+        return Err(format!(
+            "average chunk size must be between 1 and {} KiB",
+            max_kib
+        ));
+    }
+    Ok(avg_kib)
+}
+
+/// Chunk size limits derived from the target average
+#[derive(Copy, Clone)]
+struct Sizes {
+    min: usize,
+    avg: usize,
+    max: usize,
+}
+
+impl Sizes {
+    fn new(avg: usize) -> Self {
+        Self {
+            min: avg / 4,
+            avg,
+            max: avg * 4,
+        }
+    }
+
+    /// Mask with one bit per power of two of the average size, a boundary is where the hash has
+    /// these bits all zero
+    fn mask(self) -> u64 {
+        let bits = 63 - (self.avg as u64).leading_zeros();
+        (1 << bits) - 1
+    }
+}
+
+/// Splits the input into chunks, returning the chunk lengths
+type ChunkFn = fn(&[u8], Sizes) -> Vec<usize>;
+
+/// Deterministic table of well distributed 64-bit values (splitmix64)
+fn random_table(seed: u64) -> [u64; 256] {
+    let mut table = [0u64; 256];
+    let mut state = seed;
+    for value in table.iter_mut() {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        *value = z ^ (z >> 31);
+    }
+    table
+}
+
+/// Cuts chunks with a rolling hash that is reset at every chunk start, checking for a boundary
+/// once the chunk is past the minimum size
+fn chunk_with<H: RollingHash>(bytes: &[u8], sizes: Sizes, mut hasher: H) -> Vec<usize> {
+    let mask = sizes.mask();
+    let mut lengths = vec![];
+    let mut start = 0;
+
+    while start < bytes.len() {
+        let end = bytes.len().min(start + sizes.max);
+        let mut cut = end;
+        hasher.reset();
+
+        for (i, &byte) in bytes[start..end].iter().enumerate() {
+            let hash = hasher.roll(byte);
+            if i + 1 >= sizes.min && hash & mask == 0 {
+                cut = start + i + 1;
+                break;
+            }
+        }
+
+        lengths.push(cut - start);
+        start = cut;
+    }
+
+    lengths
+}
+
+trait RollingHash {
+    fn reset(&mut self);
+    fn roll(&mut self, byte: u8) -> u64;
+}
+
+/// Gear hash: every byte shifts the older ones out, giving an implicit 64 byte window
+struct Gear {
+    table: [u64; 256],
+    hash: u64,
+}
+
+impl RollingHash for Gear {
+    fn reset(&mut self) {
+        self.hash = 0;
+    }
+
+    fn roll(&mut self, byte: u8) -> u64 {
+        self.hash = (self.hash << 1).wrapping_add(self.table[byte as usize]);
+        // the top bits carry the most of the window
+        self.hash >> 32
+    }
+}
+
+/// Rabin fingerprint over GF(2) of the last `WINDOW_SIZE` bytes
+struct Rabin {
+    /// Contribution of a byte leaving the window
+    out_table: [u64; 256],
+    /// Reduction of the byte shifted above the polynomial degree
+    mod_table: [u64; 256],
+    /// Shift that leaves the top byte of the digest below the polynomial degree
+    shift: u32,
+    window: [u8; WINDOW_SIZE],
+    position: usize,
+    digest: u64,
+}
+
+fn degree(polynomial: u64) -> u32 {
+    63 - polynomial.leading_zeros()
+}
+
+fn polynomial_mod(mut x: u64, polynomial: u64) -> u64 {
+    while x != 0 && degree(x) >= degree(polynomial) {
+        x ^= polynomial << (degree(x) - degree(polynomial));
+    }
+    x
+}
+
+impl Rabin {
+    fn new(polynomial: u64) -> Self {
+        let k = degree(polynomial);
+        let mut out_table = [0u64; 256];
+        let mut mod_table = [0u64; 256];
+
+        for b in 0..256u64 {
+            let mut hash = polynomial_mod(b, polynomial);
+            for _ in 0..WINDOW_SIZE - 1 {
+                hash = polynomial_mod(hash << 8, polynomial);
+            }
+            out_table[b as usize] = hash;
+            mod_table[b as usize] = polynomial_mod(b << k, polynomial) | (b << k);
+        }
+
+        Self {
+            out_table,
+            mod_table,
+            shift: k - 8,
+            window: [0; WINDOW_SIZE],
+            position: 0,
+            digest: 0,
+        }
+    }
+}
+
+impl RollingHash for Rabin {
+    fn reset(&mut self) {
+        self.window = [0; WINDOW_SIZE];
+        self.position = 0;
+        self.digest = 0;
+    }
+
+    fn roll(&mut self, byte: u8) -> u64 {
+        let out = self.window[self.position];
+        self.window[self.position] = byte;
+        self.position = (self.position + 1) % WINDOW_SIZE;
+
+        self.digest ^= self.out_table[out as usize];
+        let index = (self.digest >> self.shift) as usize;
+        self.digest = (self.digest << 8 | u64::from(byte)) ^ self.mod_table[index];
+        self.digest
+    }
+}
+
+/// Buzhash (cyclic polynomial) of the last `WINDOW_SIZE` bytes
+struct Buzhash {
+    table: [u64; 256],
+    window: [u8; WINDOW_SIZE],
+    position: usize,
+    hash: u64,
+}
+
+impl RollingHash for Buzhash {
+    /// Starts from a window of zeros, so the zeros rolled out cancel what they contributed
+    fn reset(&mut self) {
+        self.window = [0; WINDOW_SIZE];
+        self.position = 0;
+        self.hash = (0..WINDOW_SIZE).fold(0, |hash, _| hash.rotate_left(1) ^ self.table[0]);
+    }
+
+    fn roll(&mut self, byte: u8) -> u64 {
+        let out = self.window[self.position];
+        self.window[self.position] = byte;
+        self.position = (self.position + 1) % WINDOW_SIZE;
+
+        self.hash = self.hash.rotate_left(1)
+            ^ self.table[out as usize].rotate_left(WINDOW_SIZE as u32)
+            ^ self.table[byte as usize];
+        self.hash
+    }
+}
+
+fn fastcdc(bytes: &[u8], sizes: Sizes) -> Vec<usize> {
+    fastcdc::v2020::FastCDC::new(bytes, sizes.min as u32, sizes.avg as u32, sizes.max as u32)
+        .map(|chunk| chunk.length)
+        .collect()
+}
+
+fn gear(bytes: &[u8], sizes: Sizes) -> Vec<usize> {
+    let hasher = Gear {
+        table: random_table(1),
+        hash: 0,
+    };
+    chunk_with(bytes, sizes, hasher)
+}
+
+fn rabin(bytes: &[u8], sizes: Sizes) -> Vec<usize> {
+    chunk_with(bytes, sizes, Rabin::new(RABIN_POLYNOMIAL))
+}
+
+fn buzhash(bytes: &[u8], sizes: Sizes) -> Vec<usize> {
+    let hasher = Buzhash {
+        table: random_table(2),
+        window: [0; WINDOW_SIZE],
+        position: 0,
+        hash: 0,
+    };
+    chunk_with(bytes, sizes, hasher)
+}
+
+fn chunkers() -> Vec<(&'static str, ChunkFn)> {
+    vec![
+        ("FastCDC", fastcdc),
+        ("Gear", gear),
+        ("Rabin", rabin),
+        ("Buzhash", buzhash),
+    ]
+}
+
+/// Chunk-size distribution of one chunking
+#[derive(serde::Serialize)]
+struct Distribution {
+    chunks: usize,
+    mean: f64,
+    stddev: f64,
+    p10: usize,
+    p50: usize,
+    p90: usize,
+    /// Share of the chunks that were cut at the maximum size rather than at a content boundary
+    max_cut_ratio: f64,
+}
+
+fn distribution(lengths: &[usize], sizes: Sizes) -> Distribution {
+    let mut sorted = lengths.to_vec();
+    sorted.sort_unstable();
+
+    let count = sorted.len().max(1) as f64;
+    let mean = sorted.iter().sum::<usize>() as f64 / count;
+    let variance = sorted
+        .iter()
+        .map(|&l| (l as f64 - mean).powi(2))
+        .sum::<f64>()
+        / count;
+    let percentile = |p: usize| sorted.get(sorted.len() * p / 100).copied().unwrap_or(0);
+
+    Distribution {
+        chunks: sorted.len(),
+        mean,
+        stddev: variance.sqrt(),
+        p10: percentile(10),
+        p50: percentile(50),
+        p90: percentile(90),
+        max_cut_ratio: sorted.iter().filter(|&&l| l == sizes.max).count() as f64 / count,
+    }
+}
+
+/// Content hashes of the chunks with their lengths
+fn chunk_ids(bytes: &[u8], lengths: &[usize]) -> Vec<(blake3::Hash, usize)> {
+    let mut offset = 0;
+    lengths
+        .iter()
+        .map(|&len| {
+            let id = blake3::hash(&bytes[offset..offset + len]);
+            offset += len;
+            (id, len)
+        })
+        .collect()
+}
+
+/// How the input deduplicates against the base version
+#[derive(serde::Serialize)]
+struct Dedup {
+    /// Share of the input bytes in chunks that are already in the base
+    reused_ratio: f64,
+    /// Bytes of both versions divided by the bytes of their unique chunks
+    dedup_ratio: f64,
+}
+
+fn dedup(base: &[(blake3::Hash, usize)], input: &[(blake3::Hash, usize)]) -> Dedup {
+    let base_ids = base.iter().map(|(id, _)| id).collect::<HashSet<_>>();
+    let input_len = input.iter().map(|(_, len)| len).sum::<usize>();
+    let reused = input
+        .iter()
+        .filter(|(id, _)| base_ids.contains(id))
+        .map(|(_, len)| len)
+        .sum::<usize>();
+
+    let mut unique = HashSet::new();
+    let unique_len = base
+        .iter()
+        .chain(input)
+        .filter(|(id, _)| unique.insert(*id))
+        .map(|(_, len)| len)
+        .sum::<usize>();
+    let total_len = base.iter().map(|(_, len)| len).sum::<usize>() + input_len;
+
+    Dedup {
+        reused_ratio: reused as f64 / input_len.max(1) as f64,
+        dedup_ratio: total_len as f64 / unique_len.max(1) as f64,
+    }
+}
+
+#[derive(serde::Serialize)]
+struct ChunkingResult<'a> {
+    algorithm: &'a str,
+    avg_size: usize,
+    min_size: usize,
+    max_size: usize,
+    mb_per_s: f64,
+    #[serde(flatten)]
+    distribution: Distribution,
+    #[serde(flatten)]
+    dedup: Option<Dedup>,
+}
+
+fn read(path: &Path) -> Vec<u8> {
+    std::fs::read(path).unwrap_or_else(|err| {
+        eprintln!("failed to read {}: {}", path.display(), err);
+        std::process::exit(1);
+    })
+}
+
+pub fn run(options: &Options, format: Format, filter: Option<&str>) {
+    let bytes = read(&options.input);
+    let base = options.base.as_deref().map(read);
+
+    let mut report = Report::new(
+        format,
+        Metadata::new("chunking", 1, Some(bytes.len()), "file"),
+    );
+
+    if format == Format::Csv {
+        println!("algorithm,avg size,min size,max size,MB/s,chunks,mean,stddev,p10,p50,p90,max cut ratio,reused ratio,dedup ratio");
+    }
+
+    for (name, chunk) in chunkers() {
+        if !filter.iter().all(|f| name.contains(f)) {
+            continue; // skip
+        }
+
+        for &avg_kib in &options.avg_kib {
+            let sizes = Sizes::new(avg_kib * 1024);
+
+            let start_time = Instant::now();
+            let lengths = chunk(&bytes, sizes);
+            let duration = start_time.elapsed().as_secs_f64();
+            let speed = (bytes.len() as f64) / (1024f64 * 1024f64) / duration;
+
+            assert_eq!(lengths.iter().sum::<usize>(), bytes.len());
+
+            let distribution = distribution(&lengths, sizes);
+            let dedup = base.as_ref().map(|base| {
+                let base_ids = chunk_ids(base, &chunk(base, sizes));
+                dedup(&base_ids, &chunk_ids(&bytes, &lengths))
+            });
+
+            match format {
+                Format::Text => {
+                    print!(
+                        "{:8} avg {:>5} KiB {:>6.0} MB/s {:>8} chunks  mean {:>8.0}  stddev {:>8.0}  p10 {:>7}  p50 {:>7}  p90 {:>7}  max cut {:>5.1}%",
+                        name,
+                        avg_kib,
+                        speed,
+                        distribution.chunks,
+                        distribution.mean,
+                        distribution.stddev,
+                        distribution.p10,
+                        distribution.p50,
+                        distribution.p90,
+                        distribution.max_cut_ratio * 100f64,
+                    );
+                    match &dedup {
+                        Some(d) => println!(
+                            "  reused {:>5.1}%  dedup {:>5.2}x",
+                            d.reused_ratio * 100f64,
+                            d.dedup_ratio
+                        ),
+                        None => println!(),
+                    }
+                }
+                Format::Csv => println!(
+                    "{},{},{},{},{:.0},{},{:.0},{:.0},{},{},{},{:.4},{},{}",
+                    name,
+                    sizes.avg,
+                    sizes.min,
+                    sizes.max,
+                    speed,
+                    distribution.chunks,
+                    distribution.mean,
+                    distribution.stddev,
+                    distribution.p10,
+                    distribution.p50,
+                    distribution.p90,
+                    distribution.max_cut_ratio,
+                    dedup
+                        .as_ref()
+                        .map(|d| format!("{:.4}", d.reused_ratio))
+                        .unwrap_or_default(),
+                    dedup
+                        .as_ref()
+                        .map(|d| format!("{:.4}", d.dedup_ratio))
+                        .unwrap_or_default(),
+                ),
+                Format::Json | Format::Markdown => report.add(ChunkingResult {
+                    algorithm: name,
+                    avg_size: sizes.avg,
+                    min_size: sizes.min,
+                    max_size: sizes.max,
+                    mb_per_s: speed,
+                    distribution,
+                    dedup,
+                }),
+            }
+        }
+    }
+
+    report.finish();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic pseudo-random input (xorshift64)
+    fn input(len: usize) -> Vec<u8> {
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 56) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn chunks_stay_within_bounds() {
+        let bytes = input(256 * 1024);
+        let sizes = Sizes::new(4096);
+
+        for (name, chunk) in chunkers() {
+            let lengths = chunk(&bytes, sizes);
+            let (last, rest) = lengths.split_last().unwrap();
+
+            assert_eq!(lengths.iter().sum::<usize>(), bytes.len(), "{}", name);
+            assert!(*last <= sizes.max, "{}", name);
+            assert!(
+                rest.iter().all(|&l| l >= sizes.min && l <= sizes.max),
+                "{}",
+                name
+            );
+
+            let distribution = distribution(&lengths, sizes);
+            assert!(
+                distribution.mean >= sizes.min as f64 && distribution.mean <= sizes.max as f64,
+                "{} mean {}",
+                name,
+                distribution.mean
+            );
+            assert_eq!(
+                lengths,
+                chunk(&bytes, sizes),
+                "{} isn't deterministic",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn distribution_of_known_lengths() {
+        let sizes = Sizes::new(4096);
+        let distribution = distribution(&[16384, 1024, 4096, 16384], sizes);
+
+        assert_eq!(distribution.chunks, 4);
+        assert_eq!(distribution.mean, 9472.0);
+        assert_eq!(distribution.p10, 1024);
+        assert_eq!(distribution.p50, 16384);
+        assert_eq!(distribution.max_cut_ratio, 0.5);
+
+        let empty = super::distribution(&[], sizes);
+        assert_eq!((empty.chunks, empty.mean, empty.p50), (0, 0.0, 0));
+    }
+
+    #[test]
+    fn dedup_of_known_chunks() {
+        let bytes = input(4 * 1024);
+        let ids = |indices: &[usize]| {
+            indices
+                .iter()
+                .map(|&i| (blake3::hash(&bytes[i * 1024..(i + 1) * 1024]), 1024))
+                .collect::<Vec<_>>()
+        };
+
+        // one of the three input chunks is new
+        let d = dedup(&ids(&[0, 1, 2]), &ids(&[0, 1, 3]));
+        assert!((d.reused_ratio - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(d.dedup_ratio, 1.5);
+
+        let d = dedup(&ids(&[0, 1, 2]), &ids(&[0, 1, 2]));
+        assert_eq!((d.reused_ratio, d.dedup_ratio), (1.0, 2.0));
+
+        let d = dedup(&[], &[]);
+        assert_eq!((d.reused_ratio, d.dedup_ratio), (0.0, 0.0));
+    }
+
+    /// An insertion only changes the chunks around it, the boundaries resynchronize after it
+    #[test]
+    fn insertion_reuses_most_chunks() {
+        let base = input(256 * 1024);
+        let mut bytes = base.clone();
+        bytes.splice(128 * 1024..128 * 1024, vec![0xab; 100]);
+        let sizes = Sizes::new(4096);
+
+        for (name, chunk) in chunkers() {
+            let d = dedup(
+                &chunk_ids(&base, &chunk(&base, sizes)),
+                &chunk_ids(&bytes, &chunk(&bytes, sizes)),
+            );
+            assert!(d.reused_ratio > 0.8, "{} reused {}", name, d.reused_ratio);
+        }
+    }
+}
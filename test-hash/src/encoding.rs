@@ -0,0 +1,210 @@
+//! Digest encodings, including self-describing multihashes with the multicodec code of each hash
+
+use std::convert::TryFrom;
+use structopt::clap::arg_enum;
+
+arg_enum! {
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Encoding {
+    Hex,
+    Base64,
+    Base58,
+    Base32,
+    Multihash,
+}
+}
+
+/// `(hash, multicodec name, multicodec code)` from the multicodec table, for the registered hashes
+/// whose digest is the byte string the table expects. KangarooTwelve is left out as it is run with
+/// a customization string, which the multicodec code doesn't identify
+#[rustfmt::skip]
+const MULTICODECS: &[(&str, &str, u64)] = &[
+    ("SHA-1", "sha1", 0x11),
+    ("SHA-256", "sha2-256", 0x12),
+    ("SHA-512", "sha2-512", 0x13),
+    ("SHA3-512", "sha3-512", 0x14),
+    ("SHA3-384", "sha3-384", 0x15),
+    ("SHA3-256", "sha3-256", 0x16),
+    ("SHA3-224", "sha3-224", 0x17),
+    ("SHAKE128", "shake-128", 0x18),
+    ("SHAKE256", "shake-256", 0x19),
+    ("Keccak-224", "keccak-224", 0x1a),
+    ("Keccak-256", "keccak-256", 0x1b),
+    ("Keccak256", "keccak-256", 0x1b),
+    ("Keccak-384", "keccak-384", 0x1c),
+    ("Keccak384", "keccak-384", 0x1c),
+    ("Keccak-512", "keccak-512", 0x1d),
+    ("Keccak512", "keccak-512", 0x1d),
+    ("BLAKE3", "blake3", 0x1e),
+    ("SHA-384", "sha2-384", 0x20),
+    ("MD5", "md5", 0xd5),
+    ("SHA-224", "sha2-224", 0x1013),
+    ("SHA-512-224", "sha2-512-224", 0x1014),
+    ("SHA-512-256", "sha2-512-256", 0x1015),
+    ("RIPEMD-160", "ripemd-160", 0x1053),
+    ("SM3", "sm3-256", 0x534d),
+    ("BLAKE2b-256", "blake2b-256", 0xb220),
+    ("BLAKE2b", "blake2b-512", 0xb240),
+    ("BLAKE2s", "blake2s-256", 0xb260),
+    ("Skein-256", "skein256-256", 0xb320),
+    ("Skein-512", "skein512-512", 0xb360),
+];
+
+/// Multicodec code of a hash, used as the code table of the multihashes
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Multicodec(u64);
+
+impl From<Multicodec> for u64 {
+    fn from(code: Multicodec) -> Self {
+        code.0
+    }
+}
+
+impl TryFrom<u64> for Multicodec {
+    type Error = multihash::DecodeError;
+
+    fn try_from(code: u64) -> Result<Self, Self::Error> {
+        if MULTICODECS.iter().any(|&(_, _, c)| c == code) {
+            Ok(Multicodec(code))
+        } else {
+            Err(multihash::DecodeError::UnknownCode)
+        }
+    }
+}
+
+fn multicodec(hash_name: &str) -> Option<Multicodec> {
+    MULTICODECS
+        .iter()
+        .find(|&&(name, ..)| name == hash_name)
+        .map(|&(_, _, code)| Multicodec(code))
+}
+
+/// Encodes a digest of the named hash, with the multibase prefix for all but hex. Hashes without a
+/// multicodec code can't be multihashes
+pub fn encode(encoding: Encoding, hash_name: &str, digest: &[u8]) -> String {
+    match encoding {
+        Encoding::Hex => hex::encode(digest),
+        Encoding::Base64 => multibase::encode(multibase::Base::Base64Pad, digest),
+        Encoding::Base58 => multibase::encode(multibase::Base::Base58Btc, digest),
+        Encoding::Base32 => multibase::encode(multibase::Base::Base32Lower, digest),
+        Encoding::Multihash => match multicodec(hash_name) {
+            Some(code) => multibase::encode(
+                multibase::Base::Base58Btc,
+                multihash::wrap(code, digest).as_bytes(),
+            ),
+            None => format!("(no multicodec code for {})", hash_name),
+        },
+    }
+}
+
+/// A multihash decoded back into its algorithm and digest
+pub struct Decoded {
+    pub base: multibase::Base,
+    pub multicodec: &'static str,
+    pub code: u64,
+    /// Registered hashes with this multicodec code
+    pub hashes: Vec<&'static str>,
+    pub digest: Vec<u8>,
+}
+
+/// Decodes a multibase encoded multihash, such as printed with `--encoding multihash`
+pub fn decode(input: &str) -> Result<Decoded, String> {
+    let (base, bytes) =
+        multibase::decode(input).map_err(|e| format!("invalid multibase: {}", e))?;
+    let multihash = multihash::MultihashRefGeneric::<Multicodec>::from_slice(&bytes)
+        .map_err(|e| format!("invalid multihash: {}", e))?;
+    let code = u64::from(multihash.algorithm());
+
+    let entries = MULTICODECS.iter().filter(|&&(_, _, c)| c == code);
+
+    Ok(Decoded {
+        base,
+        multicodec: entries.clone().next().map(|&(_, name, _)| name).unwrap(),
+        code,
+        hashes: entries.map(|&(hash, ..)| hash).collect(),
+        digest: multihash.digest().to_vec(),
+    })
+}
+
+/// Prints the algorithm and digest of each multihash, exiting with an error if any fails to decode
+pub fn run(multihashes: &[String]) {
+    let mut failed = false;
+
+    for input in multihashes {
+        match decode(input) {
+            Ok(decoded) => println!(
+                "{}: {} (0x{:x}, {}) {:?} {} bits {}",
+                input,
+                decoded.multicodec,
+                decoded.code,
+                decoded.hashes.join(" "),
+                decoded.base,
+                decoded.digest.len() * 8,
+                hex::encode(&decoded.digest)
+            ),
+            Err(err) => {
+                eprintln!("{}: {}", input, err);
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multihash_round_trip() {
+        // SHA-256 of "abc"
+        let digest =
+            hex::decode("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+                .unwrap();
+        let encoded = encode(Encoding::Multihash, "SHA-256", &digest);
+        assert_eq!(encoded, "zQmatYkNGZnELf8cAGdyJpUca2PyY4szai3RHyyWofNY1pY");
+
+        let decoded = decode(&encoded).unwrap();
+        assert_eq!(decoded.base, multibase::Base::Base58Btc);
+        assert_eq!((decoded.multicodec, decoded.code), ("sha2-256", 0x12));
+        assert_eq!(decoded.hashes, vec!["SHA-256"]);
+        assert_eq!(decoded.digest, digest);
+
+        // both Keccak-256 implementations share the code
+        let decoded = decode(&encode(Encoding::Multihash, "Keccak256", &[0; 32])).unwrap();
+        assert_eq!(decoded.hashes, vec!["Keccak-256", "Keccak256"]);
+    }
+
+    #[test]
+    fn hashes_without_multicodec() {
+        assert_eq!(
+            encode(Encoding::Multihash, "XXH-64", &[0; 8]),
+            "(no multicodec code for XXH-64)"
+        );
+        assert_eq!(encode(Encoding::Hex, "XXH-64", &[0xab; 2]), "abab");
+    }
+
+    #[test]
+    fn invalid_multihashes() {
+        let multihash = |bytes: &[u8]| multibase::encode(multibase::Base::Base58Btc, bytes);
+
+        for input in &["", "!abc", "z0OIl"] {
+            let err = decode(input).err().unwrap();
+            assert!(err.starts_with("invalid multibase"), "{}: {}", input, err);
+        }
+
+        for input in &[
+            // unknown code 0x99
+            multihash(&[0x99, 0x01, 0x02, 0x01, 0x02]),
+            // SHA-256 with a truncated digest
+            multihash(&[0x12, 0x20, 0x01, 0x02]),
+            multihash(&[]),
+        ] {
+            let err = decode(input).err().unwrap();
+            assert!(err.starts_with("invalid multihash"), "{}: {}", input, err);
+        }
+    }
+}
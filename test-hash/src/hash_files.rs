@@ -0,0 +1,401 @@
+//! `sha256sum`/`b3sum` style hashing of files with any of the registered hashes
+
+use rayon::prelude::*;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+
+use crate::{hashes, HashFn};
+
+#[derive(StructOpt)]
+pub struct Options {
+    /// Hash algorithm to use, as listed by `list-hashes`. Hashes with an incremental form read
+    /// files 1 MiB at a time, the others read each file into memory whole
+    #[structopt(long, default_value = "BLAKE3")]
+    algorithm: String,
+
+    /// Implementation to use, defaults to the first one registered for the algorithm
+    #[structopt(long = "impl")]
+    implementation: Option<String>,
+
+    /// Output JSON instead of sha256sum compatible lines
+    #[structopt(long, conflicts_with = "check")]
+    json: bool,
+
+    /// Read digests from the given manifest files and verify them
+    #[structopt(long)]
+    check: bool,
+
+    /// Files and directories to hash, directories are walked recursively
+    #[structopt(parse(from_os_str), required = true)]
+    paths: Vec<PathBuf>,
+}
+
+#[derive(serde::Serialize)]
+struct FileHash<'a> {
+    path: String,
+    implementation: &'a str,
+    algorithm: &'a str,
+    digest: String,
+}
+
+fn find_hash(options: &Options) -> (&'static str, &'static str, HashFn) {
+    let hash = hashes().into_iter().find(|(impl_name, hash_name, _)| {
+        hash_name.eq_ignore_ascii_case(&options.algorithm)
+            && options.implementation.iter().all(|i| i == impl_name)
+    });
+
+    hash.unwrap_or_else(|| {
+        eprintln!(
+            "no hash named '{}'{}",
+            options.algorithm,
+            options
+                .implementation
+                .as_ref()
+                .map(|i| format!(" implemented by '{}'", i))
+                .unwrap_or_default()
+        );
+        std::process::exit(1);
+    })
+}
+
+/// Files are read and hashed this much at a time by hashes with an incremental form
+const BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Incremental form of a hash, fed a file one buffer at a time
+trait Stream {
+    fn update(&mut self, bytes: &[u8]);
+    fn finish(self: Box<Self>) -> Vec<u8>;
+}
+
+struct Digest<D>(D);
+
+impl<D: sha2::Digest> Stream for Digest<D> {
+    fn update(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    fn finish(self: Box<Self>) -> Vec<u8> {
+        self.0.finalize().to_vec()
+    }
+}
+
+impl Stream for blake3::Hasher {
+    fn update(&mut self, bytes: &[u8]) {
+        blake3::Hasher::update(self, bytes);
+    }
+
+    fn finish(self: Box<Self>) -> Vec<u8> {
+        self.finalize().as_bytes().to_vec()
+    }
+}
+
+impl Stream for xxhash_rust::xxh32::Xxh32 {
+    fn update(&mut self, bytes: &[u8]) {
+        xxhash_rust::xxh32::Xxh32::update(self, bytes);
+    }
+
+    fn finish(self: Box<Self>) -> Vec<u8> {
+        crate::u32_to_vec(self.digest())
+    }
+}
+
+impl Stream for xxhash_rust::xxh64::Xxh64 {
+    fn update(&mut self, bytes: &[u8]) {
+        xxhash_rust::xxh64::Xxh64::update(self, bytes);
+    }
+
+    fn finish(self: Box<Self>) -> Vec<u8> {
+        crate::u64_to_vec(self.digest())
+    }
+}
+
+impl Stream for crc32fast::Hasher {
+    fn update(&mut self, bytes: &[u8]) {
+        crc32fast::Hasher::update(self, bytes);
+    }
+
+    fn finish(self: Box<Self>) -> Vec<u8> {
+        crate::u32_to_vec(self.finalize())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Stream for ring::digest::Context {
+    fn update(&mut self, bytes: &[u8]) {
+        ring::digest::Context::update(self, bytes);
+    }
+
+    fn finish(self: Box<Self>) -> Vec<u8> {
+        ring::digest::Context::finish(*self).as_ref().to_vec()
+    }
+}
+
+/// Incremental form of the given hash, if it has one
+#[rustfmt::skip]
+fn stream(impl_name: &str, hash_name: &str) -> Option<Box<dyn Stream>> {
+    use sha2::Digest as _;
+
+    Some(match (impl_name, hash_name) {
+        ("sha2", "SHA-224") => Box::new(Digest(sha2::Sha224::new())),
+        ("sha2", "SHA-256") => Box::new(Digest(sha2::Sha256::new())),
+        ("sha2", "SHA-384") => Box::new(Digest(sha2::Sha384::new())),
+        ("sha2", "SHA-512") => Box::new(Digest(sha2::Sha512::new())),
+        ("sha2", "SHA-512-224") => Box::new(Digest(sha2::Sha512Trunc224::new())),
+        ("sha2", "SHA-512-256") => Box::new(Digest(sha2::Sha512Trunc256::new())),
+        ("sha3", "SHA3-256") => Box::new(Digest(sha3::Sha3_256::new())),
+        ("sha3", "SHA3-384") => Box::new(Digest(sha3::Sha3_384::new())),
+        ("sha3", "SHA3-512") => Box::new(Digest(sha3::Sha3_512::new())),
+        ("sha3", "Keccak256") => Box::new(Digest(sha3::Keccak256::new())),
+        ("sha3", "Keccak384") => Box::new(Digest(sha3::Keccak384::new())),
+        ("sha3", "Keccak512") => Box::new(Digest(sha3::Keccak512::new())),
+        ("md-5", "MD5") => Box::new(Digest(md5_alt::Md5::new())),
+        ("ripemd160", "RIPEMD-160") => Box::new(Digest(ripemd160::Ripemd160::new())),
+        ("blake2b", "BLAKE2b") => Box::new(Digest(blake2::Blake2b::new())),
+        ("blake2s", "BLAKE2s") => Box::new(Digest(blake2::Blake2s::new())),
+        ("blake3", "BLAKE3") => Box::new(blake3::Hasher::new()),
+        ("xxhash-rust", "XXH-32") => Box::new(xxhash_rust::xxh32::Xxh32::new(0)),
+        ("xxhash-rust", "XXH-64") => Box::new(xxhash_rust::xxh64::Xxh64::new(0)),
+        ("crc32fast", "CRC-32") => Box::new(crc32fast::Hasher::new()),
+        #[cfg(not(target_arch = "wasm32"))]
+        ("ring", "SHA-256") => Box::new(ring::digest::Context::new(&ring::digest::SHA256)),
+        #[cfg(not(target_arch = "wasm32"))]
+        ("ring", "SHA-384") => Box::new(ring::digest::Context::new(&ring::digest::SHA384)),
+        #[cfg(not(target_arch = "wasm32"))]
+        ("ring", "SHA-512") => Box::new(ring::digest::Context::new(&ring::digest::SHA512)),
+        #[cfg(not(target_arch = "wasm32"))]
+        ("ring", "SHA-512-256") => Box::new(ring::digest::Context::new(&ring::digest::SHA512_256)),
+        _ => return None,
+    })
+}
+
+/// Hashes whose reference implementations return an integer, registered in little-endian byte
+/// order. `xxhsum` and `crc32` style tools print these as big-endian numbers. SipHash returns an
+/// integer too, but is left as the little-endian bytes its reference implementation writes
+#[rustfmt::skip]
+fn integer_output(hash_name: &str) -> bool {
+    matches!(
+        hash_name,
+        "XXH-32" | "XXH-64" | "XXH3-64" | "XXH3-64-seeded" | "XXH3-64-secret"
+        | "XXH3-128" | "XXH3-128-seeded" | "XXH3-128-secret"
+        | "SeaHash" | "t1ha0" | "t1ha1" | "aHash" | "HighwayHash"
+        | "CRC-32" | "CRC-32C" | "CRC-64/ECMA-182" | "CRC-64/XZ" | "CRC-64/NVME" | "Adler-32"
+        | "wyhash" | "rapidhash-v1" | "komihash" | "FarmHash-32" | "FarmHash-64" | "MurmurHash3-32"
+        | "FNV-1a-64"
+    )
+}
+
+/// Hex encodes a digest, with integer outputs in big-endian order
+fn display_digest(hash_name: &str, mut digest: Vec<u8>) -> String {
+    if integer_output(hash_name) {
+        digest.reverse();
+    }
+    hex::encode(digest)
+}
+
+fn stream_file(mut stream: Box<dyn Stream>, mut reader: impl BufRead) -> std::io::Result<Vec<u8>> {
+    loop {
+        let buffer = reader.fill_buf()?;
+        if buffer.is_empty() {
+            break;
+        }
+        stream.update(buffer);
+        let len = buffer.len();
+        reader.consume(len);
+    }
+
+    Ok(stream.finish())
+}
+
+fn hash_file(hash: &(&str, &str, HashFn), path: &Path) -> std::io::Result<String> {
+    let (impl_name, hash_name, hash_func) = hash;
+
+    let digest = match stream(impl_name, hash_name) {
+        Some(stream) => stream_file(
+            stream,
+            BufReader::with_capacity(BUFFER_SIZE, File::open(path)?),
+        )?,
+        // no incremental form, so the whole file has to be in memory
+        None => hash_func(&std::fs::read(path)?),
+    };
+
+    Ok(display_digest(hash_name, digest))
+}
+
+fn collect_files(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut files = vec![];
+
+    for path in paths {
+        for entry in walkdir::WalkDir::new(path).sort_by(|a, b| a.file_name().cmp(b.file_name())) {
+            match entry {
+                Ok(entry) if entry.file_type().is_file() => files.push(entry.into_path()),
+                Ok(_) => {}
+                Err(err) => eprintln!("{}", err),
+            }
+        }
+    }
+
+    files
+}
+
+fn hash_files(options: &Options) -> bool {
+    let hash = find_hash(options);
+    let (impl_name, hash_name, _) = hash;
+    let files = collect_files(&options.paths);
+
+    let results = files
+        .par_iter()
+        .map(|path| (path, hash_file(&hash, path)))
+        .collect::<Vec<_>>();
+
+    let mut ok = true;
+    let mut file_hashes = vec![];
+
+    for (path, result) in results {
+        match result {
+            Ok(digest) => file_hashes.push(FileHash {
+                path: path.display().to_string(),
+                implementation: impl_name,
+                algorithm: hash_name,
+                digest,
+            }),
+            Err(err) => {
+                eprintln!("{}: {}", path.display(), err);
+                ok = false;
+            }
+        }
+    }
+
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&file_hashes).unwrap());
+    } else {
+        for h in file_hashes {
+            println!("{}  {}", h.digest, h.path);
+        }
+    }
+
+    ok
+}
+
+/// Parses a `<digest>  <path>` line, also accepting the `*` binary mode marker
+fn parse_manifest_line(line: &str) -> Option<(&str, &str)> {
+    let (digest, path) = line.split_at(line.find(' ')?);
+    let path = path
+        .strip_prefix("  ")
+        .or_else(|| path.strip_prefix(" *"))?;
+    Some((digest, path))
+}
+
+fn check_manifests(options: &Options) -> bool {
+    let hash = find_hash(options);
+
+    let mut entries = vec![];
+    let mut ok = true;
+
+    for manifest in &options.paths {
+        let file = match File::open(manifest) {
+            Ok(file) => file,
+            Err(err) => {
+                eprintln!("{}: {}", manifest.display(), err);
+                ok = false;
+                continue;
+            }
+        };
+
+        for line in BufReader::new(file).lines() {
+            let line = line.unwrap_or_else(|err| {
+                eprintln!("{}: {}", manifest.display(), err);
+                std::process::exit(1);
+            });
+            if line.is_empty() {
+                continue;
+            }
+            match parse_manifest_line(&line) {
+                Some((digest, path)) => {
+                    entries.push((digest.to_ascii_lowercase(), path.to_string()))
+                }
+                None => {
+                    eprintln!(
+                        "{}: improperly formatted line: {}",
+                        manifest.display(),
+                        line
+                    );
+                    ok = false;
+                }
+            }
+        }
+    }
+
+    let results = entries
+        .par_iter()
+        .map(|(digest, path)| match hash_file(&hash, Path::new(path)) {
+            Ok(actual) if &actual == digest => "OK",
+            Ok(_) => "FAILED",
+            Err(_) => "FAILED open or read",
+        })
+        .collect::<Vec<_>>();
+
+    for ((_, path), result) in entries.iter().zip(results) {
+        println!("{}: {}", path, result);
+        ok &= result == "OK";
+    }
+
+    ok
+}
+
+pub fn run(options: &Options) {
+    let ok = if options.check {
+        check_manifests(options)
+    } else {
+        hash_files(options)
+    };
+
+    if !ok {
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest_lines() {
+        let digest = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        let text = format!("{}  dir/some file", digest);
+        let binary = format!("{} *dir/some file", digest);
+
+        assert_eq!(parse_manifest_line(&text), Some((digest, "dir/some file")));
+        assert_eq!(
+            parse_manifest_line(&binary),
+            Some((digest, "dir/some file"))
+        );
+        assert_eq!(parse_manifest_line(&format!("{} file", digest)), None);
+        assert_eq!(parse_manifest_line(digest), None);
+        assert_eq!(parse_manifest_line(""), None);
+    }
+
+    /// Feeding a stream uneven buffers gives the same digest as hashing everything at once
+    #[test]
+    fn streams_match_hashes() {
+        let bytes = (0..10_000).map(|i| (i * 7) as u8).collect::<Vec<_>>();
+
+        for (impl_name, hash_name, hash_func) in hashes() {
+            if let Some(stream) = stream(impl_name, hash_name) {
+                let streamed =
+                    stream_file(stream, BufReader::with_capacity(333, &bytes[..])).unwrap();
+                assert_eq!(streamed, hash_func(&bytes), "{} {}", impl_name, hash_name);
+            }
+        }
+    }
+
+    #[test]
+    fn integer_outputs_are_big_endian() {
+        let (_, _, xxh64) = hashes()
+            .into_iter()
+            .find(|(_, hash_name, _)| *hash_name == "XXH-64")
+            .unwrap();
+        assert_eq!(display_digest("XXH-64", xxh64(b"")), "ef46db3751d8e999");
+    }
+}
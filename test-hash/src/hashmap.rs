@@ -0,0 +1,368 @@
+//! `HashMap`/`HashSet` workloads with each `std::hash::Hasher` plugged in as a `BuildHasher`
+
+use std::collections::{hash_map::RandomState, HashMap, HashSet};
+use std::hash::{BuildHasher, BuildHasherDefault, Hash};
+use std::time::Instant;
+
+use crate::report::{Metadata, Report};
+use crate::Format;
+
+/// Deterministic mixing of an index into a well distributed 64-bit value (splitmix64)
+fn mix(i: u64) -> u64 {
+    let mut z = i.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+trait Key: Hash + Eq + Clone {
+    const NAME: &'static str;
+
+    fn generate(i: u64) -> Self;
+}
+
+impl Key for u64 {
+    const NAME: &'static str = "u64";
+
+    fn generate(i: u64) -> Self {
+        mix(i)
+    }
+}
+
+impl Key for String {
+    const NAME: &'static str = "string";
+
+    fn generate(i: u64) -> Self {
+        format!("key-{:x}", mix(i))
+    }
+}
+
+impl Key for uuid::Uuid {
+    const NAME: &'static str = "uuid";
+
+    fn generate(i: u64) -> Self {
+        uuid::Uuid::from_u128(u128::from(mix(i)) << 64 | u128::from(mix(!i)))
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct StructKey {
+    id: u32,
+    kind: u16,
+    flags: u8,
+    name: String,
+}
+
+impl Key for StructKey {
+    const NAME: &'static str = "struct";
+
+    fn generate(i: u64) -> Self {
+        let v = mix(i);
+        Self {
+            id: v as u32,
+            kind: (v >> 32) as u16,
+            flags: (v >> 48) as u8,
+            name: format!("item{}", i),
+        }
+    }
+}
+
+/// The operations the workloads need, implemented for both `HashMap` and `HashSet`
+trait Table<K> {
+    fn insert_key(&mut self, key: K);
+    fn has_key(&self, key: &K) -> bool;
+    fn remove_key(&mut self, key: &K) -> bool;
+}
+
+impl<K: Hash + Eq, S: BuildHasher> Table<K> for HashMap<K, u64, S> {
+    fn insert_key(&mut self, key: K) {
+        self.insert(key, 0);
+    }
+    fn has_key(&self, key: &K) -> bool {
+        self.contains_key(key)
+    }
+    fn remove_key(&mut self, key: &K) -> bool {
+        self.remove(key).is_some()
+    }
+}
+
+impl<K: Hash + Eq, S: BuildHasher> Table<K> for HashSet<K, S> {
+    fn insert_key(&mut self, key: K) {
+        self.insert(key);
+    }
+    fn has_key(&self, key: &K) -> bool {
+        self.contains(key)
+    }
+    fn remove_key(&mut self, key: &K) -> bool {
+        self.remove(key)
+    }
+}
+
+/// Operations per second for each workload
+#[derive(serde::Serialize)]
+struct WorkloadResult {
+    insert: f64,
+    lookup_hit: f64,
+    lookup_miss: f64,
+    remove: f64,
+}
+
+fn ops_per_sec(count: usize, start_time: Instant) -> f64 {
+    count as f64 / start_time.elapsed().as_secs_f64()
+}
+
+fn run_workloads<K: Key, T: Table<K>>(mut table: T, keys: &[K], miss_keys: &[K]) -> WorkloadResult {
+    let start_time = Instant::now();
+    for key in keys {
+        table.insert_key(key.clone());
+    }
+    let insert = ops_per_sec(keys.len(), start_time);
+
+    let start_time = Instant::now();
+    let hits = keys.iter().filter(|key| table.has_key(key)).count();
+    let lookup_hit = ops_per_sec(keys.len(), start_time);
+    assert_eq!(hits, keys.len());
+
+    let start_time = Instant::now();
+    let hits = miss_keys.iter().filter(|key| table.has_key(key)).count();
+    let lookup_miss = ops_per_sec(miss_keys.len(), start_time);
+    assert_eq!(hits, 0);
+
+    let start_time = Instant::now();
+    let removed = keys.iter().filter(|key| table.remove_key(key)).count();
+    let remove = ops_per_sec(keys.len(), start_time);
+    assert_eq!(removed, keys.len());
+
+    WorkloadResult {
+        insert,
+        lookup_hit,
+        lookup_miss,
+        remove,
+    }
+}
+
+struct Options<'a> {
+    format: Format,
+    filter: Option<&'a str>,
+    set: bool,
+}
+
+#[derive(serde::Serialize)]
+struct HashMapResult<'a> {
+    implementation: &'a str,
+    hash: &'a str,
+    key: &'a str,
+    collection: &'a str,
+    #[serde(flatten)]
+    ops_per_s: WorkloadResult,
+}
+
+fn run_hasher<K: Key, S: BuildHasher>(
+    options: &Options,
+    report: &mut Report,
+    impl_name: &str,
+    hash_name: &str,
+    build_hasher: S,
+    keys: &[K],
+    miss_keys: &[K],
+) {
+    if let Some(filter) = options.filter {
+        if !impl_name.contains(filter) {
+            return; // skip
+        }
+    }
+
+    let (collection, r) = if options.set {
+        let set = HashSet::with_hasher(build_hasher);
+        ("HashSet", run_workloads(set, keys, miss_keys))
+    } else {
+        let map = HashMap::with_hasher(build_hasher);
+        ("HashMap", run_workloads(map, keys, miss_keys))
+    };
+
+    let mops = |v: f64| v / 1_000_000f64;
+
+    match options.format {
+        Format::Text => {
+            println!(
+                "{:15} {:16} {:8} {:7} insert {:>6.1} Mops/s  hit {:>6.1} Mops/s  miss {:>6.1} Mops/s  remove {:>6.1} Mops/s",
+                hash_name,
+                impl_name,
+                K::NAME,
+                collection,
+                mops(r.insert),
+                mops(r.lookup_hit),
+                mops(r.lookup_miss),
+                mops(r.remove),
+            );
+        }
+        Format::Csv => {
+            println!(
+                "{},{},{},{},{:.0},{:.0},{:.0},{:.0}",
+                impl_name,
+                hash_name,
+                K::NAME,
+                collection,
+                r.insert,
+                r.lookup_hit,
+                r.lookup_miss,
+                r.remove
+            );
+        }
+        Format::Json | Format::Markdown => report.add(HashMapResult {
+            implementation: impl_name,
+            hash: hash_name,
+            key: K::NAME,
+            collection,
+            ops_per_s: r,
+        }),
+    }
+}
+
+fn run_key<K: Key>(options: &Options, report: &mut Report, count: usize) {
+    let keys = (0..count as u64).map(K::generate).collect::<Vec<_>>();
+    let miss_keys = (count as u64..2 * count as u64)
+        .map(K::generate)
+        .collect::<Vec<_>>();
+
+    run_hasher(
+        options,
+        report,
+        "std",
+        "SipHash-1-3",
+        RandomState::new(),
+        &keys,
+        &miss_keys,
+    );
+    run_hasher(
+        options,
+        report,
+        "siphasher",
+        "SipHash-1-3",
+        BuildHasherDefault::<siphasher::sip::SipHasher13>::default(),
+        &keys,
+        &miss_keys,
+    );
+    run_hasher(
+        options,
+        report,
+        "siphasher",
+        "SipHash-2-4",
+        BuildHasherDefault::<siphasher::sip::SipHasher24>::default(),
+        &keys,
+        &miss_keys,
+    );
+    run_hasher(
+        options,
+        report,
+        "twox-hash",
+        "XXH-32",
+        twox_hash::RandomXxHashBuilder32::default(),
+        &keys,
+        &miss_keys,
+    );
+    run_hasher(
+        options,
+        report,
+        "twox-hash",
+        "XXH-64",
+        twox_hash::RandomXxHashBuilder64::default(),
+        &keys,
+        &miss_keys,
+    );
+    run_hasher(
+        options,
+        report,
+        "ahash",
+        "aHash",
+        ahash::RandomState::new(),
+        &keys,
+        &miss_keys,
+    );
+    run_hasher(
+        options,
+        report,
+        "fxhash",
+        "FxHash-64",
+        BuildHasherDefault::<fxhash::FxHasher64>::default(),
+        &keys,
+        &miss_keys,
+    );
+    run_hasher(
+        options,
+        report,
+        "wyhash-hasher",
+        "wyhash",
+        BuildHasherDefault::<wyhash::WyHash>::default(),
+        &keys,
+        &miss_keys,
+    );
+    run_hasher(
+        options,
+        report,
+        "rapidhash-hasher",
+        "rapidhash-fast",
+        rapidhash::fast::RandomState::new(),
+        &keys,
+        &miss_keys,
+    );
+    run_hasher(
+        options,
+        report,
+        "komihash-hasher",
+        "komihash",
+        BuildHasherDefault::<komihash::KomiHasher>::default(),
+        &keys,
+        &miss_keys,
+    );
+    run_hasher(
+        options,
+        report,
+        "metrohash",
+        "MetroHash-64",
+        BuildHasherDefault::<metrohash::MetroHash64>::default(),
+        &keys,
+        &miss_keys,
+    );
+    run_hasher(
+        options,
+        report,
+        "farmhash-hasher",
+        "FarmHash-64",
+        BuildHasherDefault::<farmhash::FarmHasher>::default(),
+        &keys,
+        &miss_keys,
+    );
+    run_hasher(
+        options,
+        report,
+        "fnv",
+        "FNV-1a-64",
+        fnv::FnvBuildHasher::default(),
+        &keys,
+        &miss_keys,
+    );
+}
+
+pub fn run(format: Format, filter: Option<&str>, count: usize, set: bool) {
+    let options = Options {
+        format,
+        filter,
+        set,
+    };
+
+    if format == Format::Csv {
+        println!(
+            "implementation,hash,key,collection,insert ops/s,hit ops/s,miss ops/s,remove ops/s"
+        );
+    }
+
+    let mut report = Report::new(format, Metadata::new("hash-map", 1, None, "generated-keys"));
+
+    run_key::<u64>(&options, &mut report, count);
+    run_key::<String>(&options, &mut report, count);
+    run_key::<uuid::Uuid>(&options, &mut report, count);
+    run_key::<StructKey>(&options, &mut report, count);
+
+    report.finish();
+}
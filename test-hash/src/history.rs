@@ -0,0 +1,54 @@
+//! Recording of the runs to the history database and the trends of each hash over them, see
+//! `bench_common::history`
+
+use std::path::Path;
+
+use bench_common::history::{parse_window, Build, History, Trends};
+
+/// The build of test-hash, from its build script
+fn build() -> Build {
+    let mut features = vec![];
+    if cfg!(feature = "blake3-portable") {
+        features.push("blake3-portable");
+    }
+    Build {
+        tool: env!("CARGO_PKG_NAME"),
+        git_commit: env!("GIT_COMMIT"),
+        rustc: env!("RUSTC_VERSION"),
+        target: env!("TARGET"),
+        crates: include_str!(concat!(env!("OUT_DIR"), "/crates.txt")),
+        features,
+    }
+}
+
+/// Opens the history database if asked for, exiting with the error if it can't be
+pub fn open(path: Option<&Path>, command: &'static str) -> Option<History> {
+    bench_common::history::open(path, build(), command)
+}
+
+#[derive(structopt::StructOpt)]
+pub struct Options {
+    /// SQLite database the runs were recorded to with --history
+    #[structopt(parse(from_os_str))]
+    database: std::path::PathBuf,
+
+    /// Changes from the baseline larger than this fraction are flagged as step changes
+    #[structopt(long, default_value = "0.1")]
+    threshold: f64,
+
+    /// Number of earlier runs whose median is the baseline each run is compared against
+    #[structopt(long, default_value = "5", parse(try_from_str = parse_window))]
+    window: usize,
+}
+
+/// Prints each hash in the database that matches the filter over the runs, oldest first, flagging
+/// step changes with what changed in the build
+pub fn run(options: &Options, filter: Option<&str>) {
+    let trends = Trends {
+        tool: env!("CARGO_PKG_NAME"),
+        filter,
+        threshold: options.threshold,
+        window: options.window,
+    };
+    bench_common::history::run(&options.database, &trends);
+}
@@ -0,0 +1,175 @@
+//! Self-contained HTML report with SVG charts drawn with plotters: single-threaded throughput of
+//! each hash as bar charts, and throughput over input sizes as a line chart with --sweep
+
+use bench_common::html::document;
+use plotters::prelude::*;
+use std::path::Path;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+const WIDTH: u32 = 1000;
+
+/// Height of each bar of the throughput charts, which get taller with the number of hashes
+const BAR_HEIGHT: u32 = 18;
+
+#[derive(Default)]
+pub struct Charts {
+    /// Single-threaded MB/s of each hash, per cache mode
+    speeds: Vec<(String, Vec<(String, f64)>)>,
+    /// MB/s at each input size in bytes, per hash
+    sweeps: Vec<(String, Vec<(usize, f64)>)>,
+}
+
+impl Charts {
+    pub fn add_speed(&mut self, mode: &str, name: String, mb_per_s: f64) {
+        match self.speeds.iter_mut().find(|(m, _)| m == mode) {
+            Some((_, speeds)) => speeds.push((name, mb_per_s)),
+            None => self.speeds.push((mode.to_string(), vec![(name, mb_per_s)])),
+        }
+    }
+
+    pub fn add_sweep(&mut self, name: String, points: Vec<(usize, f64)>) {
+        self.sweeps.push((name, points));
+    }
+
+    /// Writes the report, exiting with the error if it can't be
+    pub fn write(&self, path: &Path) {
+        let mut sections = vec![];
+        let result = (|| -> Result<()> {
+            for (mode, speeds) in &self.speeds {
+                let title = format!("Single-threaded throughput, {} cache", mode);
+                if let Some(chart) = bar_chart(&title, speeds)? {
+                    sections.push((title, chart));
+                }
+            }
+            let title = "Single-threaded throughput by input size";
+            if let Some(chart) = line_chart(title, &self.sweeps)? {
+                sections.push((title.to_string(), chart));
+            }
+            std::fs::write(path, document("test-hash", &sections))?;
+            Ok(())
+        })();
+
+        if let Err(err) = result {
+            eprintln!("failed to write {}: {}", path.display(), err);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Horizontal bars of the speeds, fastest at the top, or nothing if there are no speeds to draw
+fn bar_chart(title: &str, speeds: &[(String, f64)]) -> Result<Option<String>> {
+    let mut speeds = speeds.to_vec();
+    speeds.sort_by(|a, b| a.1.total_cmp(&b.1));
+    let max = speeds.iter().map(|s| s.1).fold(0.0, f64::max);
+    if max <= 0.0 {
+        return Ok(None);
+    }
+    let names = speeds.iter().map(|s| s.0.clone()).collect::<Vec<_>>();
+
+    let mut svg = String::new();
+    {
+        let height = 100 + BAR_HEIGHT * speeds.len() as u32;
+        let root = SVGBackend::with_string(&mut svg, (WIDTH, height)).into_drawing_area();
+        root.fill(&WHITE)?;
+
+        let mut chart = ChartBuilder::on(&root)
+            .caption(title, ("sans-serif", 20))
+            .margin(10)
+            .x_label_area_size(40)
+            .y_label_area_size(260)
+            .build_cartesian_2d(0.0..max * 1.05, (0..speeds.len() as i32).into_segmented())?;
+
+        chart
+            .configure_mesh()
+            .disable_y_mesh()
+            .y_labels(speeds.len())
+            .y_label_formatter(&|value| match value {
+                SegmentValue::CenterOf(i) => names.get(*i as usize).cloned().unwrap_or_default(),
+                _ => String::new(),
+            })
+            .x_label_formatter(&|speed| format!("{:.0}", speed))
+            .x_desc("MB/s")
+            .draw()?;
+
+        chart.draw_series(speeds.iter().enumerate().map(|(i, (_, speed))| {
+            let i = i as i32;
+            let mut bar = Rectangle::new(
+                [
+                    (0.0, SegmentValue::Exact(i)),
+                    (*speed, SegmentValue::Exact(i + 1)),
+                ],
+                BLUE.mix(0.6).filled(),
+            );
+            bar.set_margin(2, 2, 0, 0);
+            bar
+        }))?;
+
+        root.present()?;
+    }
+    Ok(Some(svg))
+}
+
+/// A line per hash of its speed over the input sizes, with both axes logarithmic, or nothing if
+/// there are no sizes to draw
+fn line_chart(title: &str, sweeps: &[(String, Vec<(usize, f64)>)]) -> Result<Option<String>> {
+    let points = || sweeps.iter().flat_map(|(_, points)| points);
+    if points().next().is_none() {
+        return Ok(None);
+    }
+    let min_kib = points().map(|p| p.0).min().unwrap_or(1024) as f64 / 1024.0;
+    let max_kib = points().map(|p| p.0).max().unwrap_or(1024) as f64 / 1024.0;
+    let min_speed = points().map(|p| p.1).fold(f64::INFINITY, f64::min);
+    let max_speed = points().map(|p| p.1).fold(0.0, f64::max);
+
+    let mut svg = String::new();
+    {
+        // room for the legend, which lists every hash
+        let height = 400 + 18 * sweeps.len() as u32;
+        let root = SVGBackend::with_string(&mut svg, (WIDTH, height)).into_drawing_area();
+        root.fill(&WHITE)?;
+
+        let mut chart = ChartBuilder::on(&root)
+            .caption(title, ("sans-serif", 20))
+            .margin(10)
+            .x_label_area_size(40)
+            .y_label_area_size(60)
+            .build_cartesian_2d(
+                (min_kib..max_kib).log_scale(),
+                (min_speed * 0.9..max_speed * 1.1).log_scale(),
+            )?;
+
+        chart
+            .configure_mesh()
+            .x_desc("input size (KiB)")
+            .x_label_formatter(&|kib| format!("{}", kib))
+            .y_label_formatter(&|speed| format!("{:.0}", speed))
+            .y_desc("MB/s")
+            .draw()?;
+
+        for (i, (name, points)) in sweeps.iter().enumerate() {
+            let color = Palette99::pick(i);
+            chart
+                .draw_series(LineSeries::new(
+                    points
+                        .iter()
+                        .map(|&(size, speed)| (size as f64 / 1024.0, speed)),
+                    color.stroke_width(2),
+                ))?
+                .label(name.as_str())
+                .legend(move |(x, y)| {
+                    PathElement::new(vec![(x, y), (x + 20, y)], Palette99::pick(i))
+                });
+        }
+
+        chart
+            .configure_series_labels()
+            .position(SeriesLabelPosition::UpperLeft)
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()?;
+
+        root.present()?;
+    }
+    Ok(Some(svg))
+}
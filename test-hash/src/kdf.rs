@@ -0,0 +1,493 @@
+//! Password hashing and key derivation, measured in latency under configurable cost parameters
+
+use hmac::Hmac;
+use std::time::Instant;
+use structopt::StructOpt;
+
+use crate::report::{Metadata, Report};
+use crate::Format;
+
+const PASSWORD: &[u8] = b"correct horse battery staple";
+const SALT: &[u8; 16] = b"0123456789abcdef";
+const OUTPUT_LEN: usize = 32;
+
+/// bcrypt hashes the password with its terminating zero byte
+const BCRYPT_PASSWORD: &[u8] = b"correct horse battery staple\0";
+
+/// Costs accepted by the bcrypt crate, which doesn't export its limits
+const BCRYPT_COSTS: std::ops::RangeInclusive<u32> = 4..=31;
+
+/// HKDF can expand to at most 255 blocks of the hash output
+const HKDF_MAX_OUTPUT_LEN: usize = 255 * 32;
+
+/// scrypt block size, the memory grid is converted to the closest smaller power of two `N` for it
+const SCRYPT_R: u32 = 8;
+
+#[derive(StructOpt)]
+pub struct Options {
+    /// Target latency of a single password hash, used to suggest a parameter set per algorithm
+    #[structopt(long, default_value = "500")]
+    target_ms: f64,
+
+    /// Number of runs per parameter set, the median latency is reported
+    #[structopt(long, default_value = "3")]
+    runs: usize,
+
+    /// Argon2 and scrypt memory costs in KiB
+    #[structopt(long, use_delimiter = true, default_value = "19456,65536")]
+    memory_kib: Vec<u32>,
+
+    /// Argon2 iterations (time cost)
+    #[structopt(long, use_delimiter = true, default_value = "1,2,3")]
+    iterations: Vec<u32>,
+
+    /// Argon2 lanes and scrypt parallelization
+    #[structopt(long, use_delimiter = true, default_value = "1,4")]
+    parallelism: Vec<u32>,
+
+    /// bcrypt costs, the number of rounds is 2^cost
+    #[structopt(long, use_delimiter = true, default_value = "10,12")]
+    bcrypt_cost: Vec<u32>,
+
+    /// PBKDF2-HMAC-SHA256 iterations
+    #[structopt(long, use_delimiter = true, default_value = "100000,310000,600000")]
+    pbkdf2_iterations: Vec<u32>,
+
+    /// HKDF-SHA256 output lengths in bytes
+    #[structopt(long, use_delimiter = true, default_value = "32,8160")]
+    hkdf_output_len: Vec<usize>,
+}
+
+type KdfFn = Box<dyn Fn()>;
+
+/// A parameter set of an algorithm, ready to run
+struct Kdf {
+    algorithm: &'static str,
+    parameters: String,
+    /// Working memory required by the parameters
+    memory_kib: u64,
+    /// Work factor within the same memory, higher is stronger
+    work: u64,
+    /// Password hashes get a suggested parameter set, HKDF doesn't as it isn't one
+    password_hash: bool,
+    func: KdfFn,
+}
+
+fn argon2_kdf(
+    algorithm: argon2::Algorithm,
+    name: &'static str,
+    m: u32,
+    t: u32,
+    p: u32,
+) -> Result<Kdf, String> {
+    let params = argon2::Params::new(m, t, p, Some(OUTPUT_LEN))
+        .map_err(|err| format!("invalid Argon2 parameters m={} t={} p={}: {}", m, t, p, err))?;
+
+    Ok(Kdf {
+        algorithm: name,
+        parameters: format!("m={} t={} p={}", m, t, p),
+        memory_kib: u64::from(m),
+        work: u64::from(t),
+        password_hash: true,
+        func: Box::new(move || {
+            let argon2 = argon2::Argon2::new(algorithm, argon2::Version::V0x13, params.clone());
+            let mut output = [0u8; OUTPUT_LEN];
+            argon2
+                .hash_password_into(PASSWORD, SALT, &mut output)
+                .unwrap();
+        }),
+    })
+}
+
+/// Every parameter set of the grid, or an error naming the first invalid one
+fn kdfs(options: &Options) -> Result<Vec<Kdf>, String> {
+    let mut kdfs = vec![];
+
+    for &m in &options.memory_kib {
+        for &t in &options.iterations {
+            for &p in &options.parallelism {
+                kdfs.push(argon2_kdf(
+                    argon2::Algorithm::Argon2id,
+                    "Argon2id",
+                    m,
+                    t,
+                    p,
+                )?);
+                kdfs.push(argon2_kdf(argon2::Algorithm::Argon2i, "Argon2i", m, t, p)?);
+                kdfs.push(argon2_kdf(argon2::Algorithm::Argon2d, "Argon2d", m, t, p)?);
+            }
+        }
+    }
+
+    for &m in &options.memory_kib {
+        // scrypt uses 128 * r * N bytes for its large vector
+        let n = u64::from(m) * 1024 / (128 * u64::from(SCRYPT_R));
+        let log_n = 63 - n.max(2).leading_zeros() as u8;
+
+        for &p in &options.parallelism {
+            let params = scrypt::Params::new(log_n, SCRYPT_R, p).map_err(|err| {
+                format!(
+                    "invalid scrypt parameters N=2^{} r={} p={}: {}",
+                    log_n, SCRYPT_R, p, err
+                )
+            })?;
+            let memory = 128 * u64::from(SCRYPT_R) * ((1 << log_n) + u64::from(p) + 2);
+
+            kdfs.push(Kdf {
+                algorithm: "scrypt",
+                parameters: format!("N=2^{} r={} p={}", log_n, SCRYPT_R, p),
+                memory_kib: memory / 1024,
+                work: u64::from(p),
+                password_hash: true,
+                func: Box::new(move || {
+                    let mut output = [0u8; OUTPUT_LEN];
+                    scrypt::scrypt(PASSWORD, SALT, &params, &mut output).unwrap();
+                }),
+            });
+        }
+    }
+
+    for &cost in &options.bcrypt_cost {
+        if !BCRYPT_COSTS.contains(&cost) {
+            return Err(format!(
+                "invalid bcrypt cost {}: must be between {} and {}",
+                cost,
+                BCRYPT_COSTS.start(),
+                BCRYPT_COSTS.end()
+            ));
+        }
+
+        kdfs.push(Kdf {
+            algorithm: "bcrypt",
+            parameters: format!("cost={}", cost),
+            // 4 S-boxes of 256 32-bit words and the 18 word P-array
+            memory_kib: 4,
+            work: 1 << cost,
+            password_hash: true,
+            func: Box::new(move || {
+                let mut output = [0u8; 24];
+                bcrypt::bcrypt(cost, SALT, BCRYPT_PASSWORD, &mut output);
+            }),
+        });
+    }
+
+    for &iterations in &options.pbkdf2_iterations {
+        if iterations == 0 {
+            return Err("invalid PBKDF2 iteration count 0: must be at least 1".to_string());
+        }
+
+        kdfs.push(Kdf {
+            algorithm: "PBKDF2-HMAC-SHA256",
+            parameters: format!("i={}", iterations),
+            memory_kib: 0,
+            work: u64::from(iterations),
+            password_hash: true,
+            func: Box::new(move || {
+                let mut output = [0u8; OUTPUT_LEN];
+                pbkdf2::pbkdf2::<Hmac<sha2::Sha256>>(PASSWORD, SALT, iterations, &mut output);
+            }),
+        });
+    }
+
+    for &len in &options.hkdf_output_len {
+        if len > HKDF_MAX_OUTPUT_LEN {
+            return Err(format!(
+                "invalid HKDF-SHA256 output length {}: must be at most {}",
+                len, HKDF_MAX_OUTPUT_LEN
+            ));
+        }
+
+        kdfs.push(Kdf {
+            algorithm: "HKDF-SHA256",
+            parameters: format!("L={}", len),
+            memory_kib: 0,
+            work: len as u64,
+            password_hash: false,
+            func: Box::new(move || {
+                let mut output = vec![0u8; len];
+                hkdf::Hkdf::<sha2::Sha256>::new(Some(SALT), PASSWORD)
+                    .expand(b"test-hash", &mut output)
+                    .unwrap();
+            }),
+        });
+    }
+
+    Ok(kdfs)
+}
+
+/// Check every algorithm against a known answer from its specification
+pub fn verify() {
+    // RFC 9106 section 5, with a secret and associated data
+    let mut params = argon2::ParamsBuilder::new();
+    params
+        .m_cost(32)
+        .and_then(|p| p.t_cost(3))
+        .and_then(|p| p.p_cost(4))
+        .and_then(|p| p.data(&[4; 12]))
+        .and_then(|p| p.output_len(32))
+        .unwrap();
+    let params = params.params().unwrap();
+
+    for &(algorithm, expected) in &[
+        (
+            argon2::Algorithm::Argon2d,
+            "512b391b6f1162975371d30919734294f868e3be3984f3c1a13a4db9fabe4acb",
+        ),
+        (
+            argon2::Algorithm::Argon2i,
+            "c814d9d1dc7f37aa13f0d77f2494bda1c8de6b016dd388d29952a4c4672b6ce8",
+        ),
+        (
+            argon2::Algorithm::Argon2id,
+            "0d640df58d78766c08c037a34a8b53c9d01ef0452d75b65eb52520e96b01e659",
+        ),
+    ] {
+        let argon2 = argon2::Argon2::new_with_secret(
+            &[3; 8],
+            algorithm,
+            argon2::Version::V0x13,
+            params.clone(),
+        )
+        .unwrap();
+        let mut output = [0u8; 32];
+        argon2
+            .hash_password_into(&[1; 32], &[2; 16], &mut output)
+            .unwrap();
+        assert_eq!(hex::encode(output), expected, "{:?}", algorithm);
+    }
+
+    // RFC 7914 section 12, the second vector
+    let mut output = [0u8; 64];
+    scrypt::scrypt(
+        b"password",
+        b"NaCl",
+        &scrypt::Params::new(10, 8, 16).unwrap(),
+        &mut output,
+    )
+    .unwrap();
+    assert_eq!(
+        hex::encode(&output[..]),
+        "fdbabe1c9d3472007856e7190d01e9fe7c6ad7cbc8237830e77376634b3731622eaf30d92e22a3886ff109279d9830dac727afb94a83ee6d8360cbdfa2cc0640",
+        "scrypt"
+    );
+
+    // OpenBSD bcrypt regression test
+    assert!(
+        bcrypt::verify(
+            "U*U",
+            "$2a$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW"
+        )
+        .unwrap(),
+        "bcrypt"
+    );
+
+    // RFC 7914 section 11, the first vector
+    let mut output = [0u8; 64];
+    pbkdf2::pbkdf2::<Hmac<sha2::Sha256>>(b"passwd", b"salt", 1, &mut output);
+    assert_eq!(
+        hex::encode(&output[..]),
+        "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc49ca9cccf179b645991664b39d77ef317c71b845b1e30bd509112041d3a19783",
+        "PBKDF2-HMAC-SHA256"
+    );
+
+    // RFC 5869 test case 1
+    let mut output = [0u8; 42];
+    hkdf::Hkdf::<sha2::Sha256>::new(
+        Some(&hex::decode("000102030405060708090a0b0c").unwrap()),
+        &[0x0b; 22],
+    )
+    .expand(&hex::decode("f0f1f2f3f4f5f6f7f8f9").unwrap(), &mut output)
+    .unwrap();
+    assert_eq!(
+        hex::encode(&output[..]),
+        "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865",
+        "HKDF-SHA256"
+    );
+}
+
+#[derive(serde::Serialize)]
+struct KdfResult<'a> {
+    algorithm: &'a str,
+    parameters: &'a str,
+    memory_kib: u64,
+    latency_ms: f64,
+    ops_per_s: f64,
+    suggested: bool,
+}
+
+struct Measurement {
+    kdf: Kdf,
+    latency_ms: f64,
+}
+
+fn measure(kdf: Kdf, runs: usize) -> Measurement {
+    let mut latencies = (0..runs.max(1))
+        .map(|_| {
+            let start_time = Instant::now();
+            (kdf.func)();
+            start_time.elapsed().as_secs_f64() * 1000f64
+        })
+        .collect::<Vec<_>>();
+    latencies.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    Measurement {
+        latency_ms: latencies[latencies.len() / 2],
+        kdf,
+    }
+}
+
+/// The strongest parameter set of each password hash within the target latency: the most memory,
+/// then the most work, then the lowest latency
+fn suggestions(measurements: &[Measurement], target_ms: f64) -> Vec<usize> {
+    let mut suggested: Vec<usize> = vec![];
+
+    for (index, m) in measurements.iter().enumerate() {
+        if !m.kdf.password_hash || m.latency_ms > target_ms {
+            continue;
+        }
+
+        let key = |m: &Measurement| (m.kdf.memory_kib, m.kdf.work, -m.latency_ms);
+
+        match suggested
+            .iter_mut()
+            .find(|s| measurements[**s].kdf.algorithm == m.kdf.algorithm)
+        {
+            Some(best) => {
+                if key(m) > key(&measurements[*best]) {
+                    *best = index;
+                }
+            }
+            None => suggested.push(index),
+        }
+    }
+
+    suggested
+}
+
+pub fn run(options: &Options, format: Format, filter: Option<&str>, threads: usize) {
+    let kdfs = kdfs(options).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    });
+
+    let measurements = kdfs
+        .into_iter()
+        .filter(|kdf| filter.iter().all(|f| kdf.algorithm.contains(f)))
+        .map(|kdf| measure(kdf, options.runs))
+        .collect::<Vec<_>>();
+
+    let suggested = suggestions(&measurements, options.target_ms);
+
+    let mut report = Report::new(format, Metadata::new("kdf", threads, None, "password"));
+
+    if format == Format::Csv {
+        println!("algorithm,parameters,memory KiB,latency ms,ops/s,suggested");
+    }
+
+    for (index, m) in measurements.iter().enumerate() {
+        let ops_per_s = 1000f64 / m.latency_ms;
+        let is_suggested = suggested.contains(&index);
+
+        match format {
+            Format::Text => println!(
+                "{:18} {:24} {:>8} KiB {:>10.3} ms {:>10.1} ops/s",
+                m.kdf.algorithm, m.kdf.parameters, m.kdf.memory_kib, m.latency_ms, ops_per_s
+            ),
+            Format::Csv => println!(
+                "{},{},{},{:.3},{:.1},{}",
+                m.kdf.algorithm,
+                m.kdf.parameters,
+                m.kdf.memory_kib,
+                m.latency_ms,
+                ops_per_s,
+                is_suggested
+            ),
+            Format::Json | Format::Markdown => report.add(KdfResult {
+                algorithm: m.kdf.algorithm,
+                parameters: &m.kdf.parameters,
+                memory_kib: m.kdf.memory_kib,
+                latency_ms: m.latency_ms,
+                ops_per_s,
+                suggested: is_suggested,
+            }),
+        }
+    }
+
+    report.finish();
+
+    if format == Format::Text {
+        println!();
+        println!("suggested for a {} ms target latency:", options.target_ms);
+        for m in suggested.iter().map(|&index| &measurements[index]) {
+            println!(
+                "{:18} {:24} {:>8} KiB {:>10.3} ms",
+                m.kdf.algorithm, m.kdf.parameters, m.kdf.memory_kib, m.latency_ms
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vectors() {
+        verify();
+    }
+
+    #[test]
+    fn invalid_grids() {
+        for args in &[
+            &["--memory-kib", "1"][..],
+            &["--parallelism", "0"],
+            &["--bcrypt-cost", "3"],
+            &["--bcrypt-cost", "32"],
+            &["--pbkdf2-iterations", "0"],
+            &["--hkdf-output-len", "8161"],
+        ] {
+            let options = Options::from_iter(std::iter::once(&"kdf").chain(args.iter()));
+            assert!(kdfs(&options).is_err(), "{:?}", args);
+        }
+
+        assert!(kdfs(&Options::from_iter(&["kdf"])).is_ok());
+    }
+
+    fn measurement(
+        algorithm: &'static str,
+        memory_kib: u64,
+        work: u64,
+        latency_ms: f64,
+    ) -> Measurement {
+        Measurement {
+            kdf: Kdf {
+                algorithm,
+                parameters: String::new(),
+                memory_kib,
+                work,
+                password_hash: algorithm != "HKDF-SHA256",
+                func: Box::new(|| {}),
+            },
+            latency_ms,
+        }
+    }
+
+    #[test]
+    fn suggests_the_strongest_within_target() {
+        let measurements = [
+            measurement("Argon2id", 19456, 2, 100.0),
+            // more memory wins over more work
+            measurement("Argon2id", 65536, 1, 300.0),
+            measurement("Argon2id", 19456, 3, 200.0),
+            // over the target latency
+            measurement("Argon2id", 65536, 2, 600.0),
+            // same work, the faster one wins
+            measurement("bcrypt", 4, 1024, 80.0),
+            measurement("bcrypt", 4, 1024, 60.0),
+            measurement("HKDF-SHA256", 0, 32, 0.1),
+            measurement("PBKDF2-HMAC-SHA256", 0, 600000, 900.0),
+        ];
+
+        assert_eq!(suggestions(&measurements, 500.0), vec![1, 5]);
+    }
+}
@@ -0,0 +1,176 @@
+//! Keyed hashes and message authentication codes
+
+use crate::HashFn;
+use std::convert::TryInto;
+use std::hash::Hasher;
+
+type MacFn = Box<dyn Fn(&[u8], &[u8]) -> Vec<u8> + Send + Sync>;
+
+/// Key used when benchmarking, the vectors in `test_vectors` use their own keys
+const BENCH_KEY: &[u8; 32] = b"a 32 byte key used for benchmark";
+
+/// Customization string used for KMAC, matching the NIST SP 800-185 samples
+const KMAC_CUSTOMIZATION: &[u8] = b"My Tagged Application";
+
+fn kmac(mut mac: tiny_keccak::Kmac, output_len: usize, b: &[u8]) -> Vec<u8> {
+    use tiny_keccak::Hasher;
+
+    let mut output = vec![0u8; output_len];
+    mac.update(b);
+    mac.finalize(&mut output);
+    output
+}
+
+fn hmac<D>(key: &[u8], b: &[u8]) -> Vec<u8>
+where
+    D: blake2::digest::Update
+        + blake2::digest::BlockInput
+        + blake2::digest::FixedOutput
+        + blake2::digest::Reset
+        + Default
+        + Clone,
+{
+    use hmac::{Mac, NewMac};
+
+    let mut mac = hmac::Hmac::<D>::new_varkey(key).unwrap();
+    mac.update(b);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Implementation, algorithm and a function of `(key, message)`
+#[rustfmt::skip]
+fn mac_fns() -> Vec<(&'static str, &'static str, MacFn)> {
+    vec![
+        // hmac
+        ( "hmac", "HMAC-SHA256", Box::new(hmac::<sha2::Sha256>) ),
+        ( "hmac", "HMAC-SHA512", Box::new(hmac::<sha2::Sha512>) ),
+
+        // ring
+        #[cfg(not(target_arch = "wasm32"))]
+        ( "ring", "HMAC-SHA256", Box::new(|k, b| {
+            let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA256, k);
+            ring::hmac::sign(&key, b).as_ref().to_vec()
+        }) ),
+        #[cfg(not(target_arch = "wasm32"))]
+        ( "ring", "HMAC-SHA512", Box::new(|k, b| {
+            let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA512, k);
+            ring::hmac::sign(&key, b).as_ref().to_vec()
+        }) ),
+
+        // blake2
+        ( "blake2b", "BLAKE2b-keyed", Box::new(|k, b| {
+            use blake2::digest::{Update, VariableOutput};
+            let mut hasher = blake2::VarBlake2b::new_keyed(k, 64);
+            hasher.update(b);
+            hasher.finalize_boxed().to_vec()
+        }) ),
+        ( "blake2s", "BLAKE2s-keyed", Box::new(|k, b| {
+            use blake2::digest::{Update, VariableOutput};
+            let mut hasher = blake2::VarBlake2s::new_keyed(&k[..32], 32);
+            hasher.update(b);
+            hasher.finalize_boxed().to_vec()
+        }) ),
+
+        // blake2b-simd / blake2s-simd
+        ( "blake2b-simd", "BLAKE2b-keyed", Box::new(|k, b| blake2b_simd::Params::new().key(k).hash(b).as_bytes().to_vec()) ),
+        ( "blake2s-simd", "BLAKE2s-keyed", Box::new(|k, b| blake2s_simd::Params::new().key(&k[..32]).hash(b).as_bytes().to_vec()) ),
+
+        // blake3
+        ( "blake3", "BLAKE3-keyed", Box::new(|k, b| blake3::keyed_hash(k[..32].try_into().unwrap(), b).as_bytes().to_vec()) ),
+
+        // tiny-keccak
+        ( "tiny-keccak", "KMAC128", Box::new(|k, b| kmac(tiny_keccak::Kmac::v128(k, KMAC_CUSTOMIZATION), 32, b)) ),
+        ( "tiny-keccak", "KMAC256", Box::new(|k, b| kmac(tiny_keccak::Kmac::v256(k, KMAC_CUSTOMIZATION), 64, b)) ),
+
+        // siphasher
+        ( "siphasher", "SipHash-2-4", Box::new(|k, b| {
+            let mut hasher = siphasher::sip::SipHasher24::new_with_key(k[..16].try_into().unwrap());
+            hasher.write(b);
+            hasher.finish().to_le_bytes().to_vec()
+        }) ),
+        ( "siphasher", "SipHash-2-4-128", Box::new(|k, b| {
+            use siphasher::sip128::Hasher128;
+            let mut hasher = siphasher::sip128::SipHasher24::new_with_key(k[..16].try_into().unwrap());
+            hasher.write(b);
+            hasher.finish128().as_bytes().to_vec()
+        }) ),
+    ]
+}
+
+fn sequence(len: u8) -> Vec<u8> {
+    (0..len).collect()
+}
+
+/// Algorithm, key, message and the expected hex encoded output
+#[rustfmt::skip]
+fn test_vectors() -> Vec<(&'static str, Vec<u8>, Vec<u8>, &'static str)> {
+    vec![
+        // RFC 4231, test case 2
+        ( "HMAC-SHA256", b"Jefe".to_vec(), b"what do ya want for nothing?".to_vec(),
+          "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843" ),
+        ( "HMAC-SHA512", b"Jefe".to_vec(), b"what do ya want for nothing?".to_vec(),
+          "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea2505549758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737" ),
+
+        // BLAKE2 reference keyed KAT, empty input
+        ( "BLAKE2b-keyed", sequence(64), vec![],
+          "10ebb67700b1868efb4417987acf4690ae9d972fb7a590c2f02871799aaa4786b5e996e8f0f4eb981fc214b005f42d2ff4233499391653df7aefcbc13fc51568" ),
+        ( "BLAKE2s-keyed", sequence(32), vec![],
+          "48a8997da407876b3d79c0d92325ad3b89cbb754d86ab71aee047ad345fd2c49" ),
+
+        // BLAKE3 reference test vectors, empty input
+        ( "BLAKE3-keyed", b"whats the Elvish word for friend".to_vec(), vec![],
+          "92b2b75604ed3c761f9d6f62392c8a9227ad0ea3f09573e783f1498a4ed60d26" ),
+
+        // NIST SP 800-185 KMAC samples #2 and #4
+        ( "KMAC128", (0x40..0x60).collect(), sequence(4),
+          "3b1fba963cd8b0b59e8c1a6d71888b7143651af8ba0a7070c0979e2811324aa5" ),
+        ( "KMAC256", (0x40..0x60).collect(), sequence(4),
+          "20c570c31346f703c9ac36c61c03cb64c3970d0cfc787e9b79599d273a68d2f7f69d4cc3de9d104a351689f27cf6f5951f0103f33f4f24871024d9c27773a8dd" ),
+
+        // SipHash reference vectors, empty input
+        ( "SipHash-2-4", sequence(16), vec![],
+          "310e0edd47db6f72" ),
+        ( "SipHash-2-4-128", sequence(16), vec![],
+          "a3817f04ba25a8e66df67214c7550293" ),
+    ]
+}
+
+/// Check every MAC implementation against the test vectors for its algorithm
+pub fn verify() {
+    let vectors = test_vectors();
+
+    for (impl_name, mac_name, mac_func) in mac_fns() {
+        let mut verified = false;
+
+        for (_, key, message, expected) in vectors.iter().filter(|(name, ..)| *name == mac_name) {
+            let output = hex::encode(mac_func(key, message));
+            assert_eq!(
+                &output, expected,
+                "{} {} doesn't match test vector",
+                impl_name, mac_name
+            );
+            verified = true;
+        }
+
+        assert!(verified, "{} {} has no test vector", impl_name, mac_name);
+    }
+}
+
+/// MACs keyed with a fixed benchmark key, usable with `perf_test`
+pub fn macs() -> Vec<(&'static str, &'static str, HashFn)> {
+    mac_fns()
+        .into_iter()
+        .map(|(impl_name, mac_name, mac_func)| {
+            let hash_func: HashFn = Box::new(move |b| mac_func(BENCH_KEY, b));
+            (impl_name, mac_name, hash_func)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_vectors() {
+        super::verify();
+    }
+}
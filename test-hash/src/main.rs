@@ -1,5 +1,6 @@
 #![allow(dead_code)]
 
+use bench_common::{cache, counters, profile};
 use blake2::Digest as BlakeDigest;
 use rayon::prelude::*;
 use std::collections::HashSet;
@@ -7,6 +8,22 @@ use std::hash::Hasher;
 use std::time::Instant;
 use structopt::{clap::arg_enum, StructOpt};
 
+mod alignment;
+mod bao_stream;
+mod chunking;
+mod encoding;
+mod hash_files;
+mod hashmap;
+mod history;
+mod html;
+mod kdf;
+mod mac;
+mod registry;
+mod report;
+mod simd;
+mod vectors;
+mod xof;
+
 fn u32_to_vec(v: u32) -> Vec<u8> {
     use byteorder::{LittleEndian, WriteBytesExt};
 
@@ -31,11 +48,15 @@ fn u128_to_vec(v: u128) -> Vec<u8> {
     vec
 }
 
-fn tiny_keccak_hash(mut hasher: impl tiny_keccak::Hasher, bytes: &[u8]) -> Vec<u8> {
-    let mut output = [0u8; 32];
+fn tiny_keccak_hash(
+    mut hasher: impl tiny_keccak::Hasher,
+    bytes: &[u8],
+    output_len: usize,
+) -> Vec<u8> {
+    let mut output = vec![0u8; output_len];
     hasher.update(bytes);
     hasher.finalize(&mut output);
-    output.to_vec()
+    output
 }
 
 fn std_hasher(mut hasher: impl std::hash::Hasher, bytes: &[u8]) -> Vec<u8> {
@@ -43,9 +64,36 @@ fn std_hasher(mut hasher: impl std::hash::Hasher, bytes: &[u8]) -> Vec<u8> {
     u64_to_vec(hasher.finish())
 }
 
+fn std_hasher128(
+    mut hasher: impl std::hash::Hasher + siphasher::sip128::Hasher128,
+    bytes: &[u8],
+) -> Vec<u8> {
+    hasher.write(bytes);
+    hasher.finish128().as_bytes().to_vec()
+}
+
+/// CRC-64/NVME isn't in the catalog of the `crc` version we depend on
+const CRC_64_NVME_ALGORITHM: crc::Algorithm<u64> = crc::Algorithm {
+    width: 64,
+    poly: 0xad93d23594c93659,
+    init: 0xffffffffffffffff,
+    refin: true,
+    refout: true,
+    xorout: 0xffffffffffffffff,
+    check: 0xae8b14860a799888,
+    residue: 0xf310303b2b6f6e42,
+};
+
+static CRC_32: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
+static CRC_32C: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_ISCSI);
+static CRC_64_ECMA_182: crc::Crc<u64> = crc::Crc::<u64>::new(&crc::CRC_64_ECMA_182);
+static CRC_64_XZ: crc::Crc<u64> = crc::Crc::<u64>::new(&crc::CRC_64_XZ);
+static CRC_64_NVME: crc::Crc<u64> = crc::Crc::<u64>::new(&CRC_64_NVME_ALGORITHM);
+
+type HashFn = Box<dyn Fn(&[u8]) -> Vec<u8> + Send + Sync>;
+
 #[rustfmt::skip]
-#[allow(clippy::type_complexity)]
-fn hashes() -> Vec<(&'static str, &'static str, Box<dyn Fn(&[u8]) -> Vec<u8> + Send + Sync>)> {
+fn hashes() -> Vec<(&'static str, &'static str, HashFn)> {
     vec![
         // twox-hash
         ( 
@@ -65,17 +113,22 @@ fn hashes() -> Vec<(&'static str, &'static str, Box<dyn Fn(&[u8]) -> Vec<u8> + S
             }),
         ),
 
-        // xxhrs - disable as doesn't compile on Windows
-    /*        
-        #[cfg(not(target_arch = "wasm32"))]
-        ( "xxhrs", "XXH-32", Box::new(|b| u32_to_vec(xxhrs::XXH32::hash(&b)) ), ),
-        #[cfg(not(target_arch = "wasm32"))]
-        ( "xxhrs", "XXH-64", Box::new(|b| u64_to_vec(xxhrs::XXH64::hash(&b)) ), ),
-        #[cfg(not(target_arch = "wasm32"))]
-        ( "xxhrs", "XXH3-64", Box::new(|b| u64_to_vec(xxhrs::XXH3_64::hash(&b)) ), ),
-        #[cfg(not(target_arch = "wasm32"))]
-        ( "xxhrs", "XXH3-128", Box::new(|b| u128_to_vec(xxhrs::XXH3_128::hash(&b)) ), ),
-    */
+        // xxhash-rust
+        ( "xxhash-rust", "XXH-32", Box::new(|b| u32_to_vec(xxhash_rust::xxh32::xxh32(b, 0))) ),
+        ( "xxhash-rust", "XXH-64", Box::new(|b| u64_to_vec(xxhash_rust::xxh64::xxh64(b, 0))) ),
+        ( "xxhash-rust", "XXH3-64", Box::new(|b| u64_to_vec(xxhash_rust::xxh3::xxh3_64(b))) ),
+        ( "xxhash-rust", "XXH3-64-seeded", Box::new(|b| u64_to_vec(xxhash_rust::xxh3::xxh3_64_with_seed(b, vectors::XXH3_64_SEED))) ),
+        {
+            let secret = vectors::xxh3_secret();
+            ( "xxhash-rust", "XXH3-64-secret", Box::new(move |b| u64_to_vec(xxhash_rust::xxh3::xxh3_64_with_secret(b, &secret))) )
+        },
+        ( "xxhash-rust", "XXH3-128", Box::new(|b| u128_to_vec(xxhash_rust::xxh3::xxh3_128(b))) ),
+        ( "xxhash-rust", "XXH3-128-seeded", Box::new(|b| u128_to_vec(xxhash_rust::xxh3::xxh3_128_with_seed(b, vectors::XXH3_128_SEED))) ),
+        {
+            let secret = vectors::xxh3_secret();
+            ( "xxhash-rust", "XXH3-128-secret", Box::new(move |b| u128_to_vec(xxhash_rust::xxh3::xxh3_128_with_secret(b, &secret))) )
+        },
+
         // meowhash
         #[cfg(target_arch = "x86_64")]
         (
@@ -103,9 +156,11 @@ fn hashes() -> Vec<(&'static str, &'static str, Box<dyn Fn(&[u8]) -> Vec<u8> + S
         }) ),
 
         // sha2
+        ( "sha2", "SHA-224", Box::new(|b| sha2::Sha224::digest(b).to_vec()) ),
         ( "sha2", "SHA-256", Box::new(|b| sha2::Sha256::digest(b).to_vec()) ),
         ( "sha2", "SHA-384", Box::new(|b| sha2::Sha384::digest(b).to_vec()) ),
         ( "sha2", "SHA-512", Box::new(|b| sha2::Sha512::digest(b).to_vec()) ),
+        ( "sha2", "SHA-512-224", Box::new(|b| sha2::Sha512Trunc224::digest(b).to_vec()) ),
         ( "sha2", "SHA-512-256", Box::new(|b| sha2::Sha512Trunc256::digest(b).to_vec()) ),
         
         // sha3
@@ -116,11 +171,23 @@ fn hashes() -> Vec<(&'static str, &'static str, Box<dyn Fn(&[u8]) -> Vec<u8> + S
         ( "sha3", "Keccak384", Box::new(|b| sha3::Keccak384::digest(b).to_vec()) ),
         ( "sha3", "Keccak512", Box::new(|b| sha3::Keccak512::digest(b).to_vec()) ),
 
+        // other RustCrypto hashes
+        ( "ripemd160", "RIPEMD-160", Box::new(|b| ripemd160::Ripemd160::digest(b).to_vec()) ),
+        ( "sm3", "SM3", Box::new(|b| sm3::Sm3::digest(b).to_vec()) ),
+        ( "whirlpool", "Whirlpool", Box::new(|b| whirlpool::Whirlpool::digest(b).to_vec()) ),
+        ( "streebog", "Streebog-256", Box::new(|b| streebog::Streebog256::digest(b).to_vec()) ),
+        ( "streebog", "Streebog-512", Box::new(|b| streebog::Streebog512::digest(b).to_vec()) ),
+        ( "groestl", "Groestl-256", Box::new(|b| groestl::Groestl256::digest(b).to_vec()) ),
+        ( "groestl", "Groestl-512", Box::new(|b| groestl::Groestl512::digest(b).to_vec()) ),
+        ( "skein-hash", "Skein-256", Box::new(|b| skein_hash::Skein256::<skein_hash::digest::consts::U32>::digest(b).to_vec()) ),
+        ( "skein-hash", "Skein-512", Box::new(|b| skein_hash::Skein512::<skein_hash::digest::consts::U64>::digest(b).to_vec()) ),
+        ( "tiger", "Tiger", Box::new(|b| tiger::Tiger::digest(b).to_vec()) ),
+
         // siphasher
         ( "siphasher", "SipHash-1-3", Box::new(|b| std_hasher(siphasher::sip::SipHasher13::new(), b)) ),
         ( "siphasher", "SipHash-2-4", Box::new(|b| std_hasher(siphasher::sip::SipHasher24::new(), b)) ),
-        ( "siphasher", "SipHash-1-3-128", Box::new(|b| std_hasher(siphasher::sip128::SipHasher13::new(), b)) ),
-        ( "siphasher", "SipHash-2-4-128", Box::new(|b| std_hasher(siphasher::sip128::SipHasher24::new(), b)) ),
+        ( "siphasher", "SipHash-1-3-128", Box::new(|b| std_hasher128(siphasher::sip128::SipHasher13::new(), b)) ),
+        ( "siphasher", "SipHash-2-4-128", Box::new(|b| std_hasher128(siphasher::sip128::SipHasher24::new(), b)) ),
 
         // highway
         ( "highway", "HighwayHash", Box::new(|b| {
@@ -146,6 +213,30 @@ fn hashes() -> Vec<(&'static str, &'static str, Box<dyn Fn(&[u8]) -> Vec<u8> + S
             hash
         })),
 
+        // highway without the SSE 4.1 and AVX2 implementations
+        ( "highway-portable", "HighwayHash", Box::new(|b| {
+            use highway::HighwayHash;
+            u64_to_vec(highway::PortableHash::new(highway::Key::default()).hash64(b))
+        })),
+        ( "highway-portable", "HighwayHash-128", Box::new(|b| {
+            use highway::HighwayHash;
+            let h = highway::PortableHash::new(highway::Key::default()).hash128(b);
+            let mut hash = vec![];
+            hash.extend(&h[0].to_le_bytes());
+            hash.extend(&h[1].to_le_bytes());
+            hash
+        })),
+        ( "highway-portable", "HighwayHash-256", Box::new(|b| {
+            use highway::HighwayHash;
+            let h = highway::PortableHash::new(highway::Key::default()).hash256(b);
+            let mut hash = vec![];
+            hash.extend(&h[0].to_le_bytes());
+            hash.extend(&h[1].to_le_bytes());
+            hash.extend(&h[2].to_le_bytes());
+            hash.extend(&h[3].to_le_bytes());
+            hash
+        })),
+
         // blake2
         ( "blake2b", "BLAKE2b", Box::new(|b| blake2::Blake2b::digest(b).to_vec()) ),
         ( "blake2s", "BLAKE2s", Box::new(|b| blake2::Blake2s::digest(b).to_vec()) ),
@@ -188,6 +279,42 @@ fn hashes() -> Vec<(&'static str, &'static str, Box<dyn Fn(&[u8]) -> Vec<u8> + S
         ( "blake2s-simd", "BLAKE2s",  Box::new(|b| blake2s_simd::blake2s(b).as_bytes().to_vec()) ),
         ( "blake2s-simd", "BLAKE2sp", Box::new(|b| blake2s_simd::blake2sp::blake2sp(b).as_bytes().to_vec()) ),
 
+        // blake2b-simd and blake2s-simd forced to their portable implementations, through the
+        // crates' hooks for their own benchmarks
+
+        (
+            "blake2b-simd-portable", "BLAKE2b",
+            Box::new(|b| {
+                let mut params = blake2b_simd::Params::new();
+                blake2b_simd::benchmarks::force_portable(&mut params);
+                params.hash(b).as_bytes().to_vec()
+            })
+        ),
+        (
+            "blake2b-simd-portable", "BLAKE2bp",
+            Box::new(|b| {
+                let mut params = blake2b_simd::blake2bp::Params::new();
+                blake2b_simd::benchmarks::force_portable_blake2bp(&mut params);
+                params.hash(b).as_bytes().to_vec()
+            })
+        ),
+        ( 
+            "blake2s-simd-portable", "BLAKE2s",
+            Box::new(|b| {
+                let mut params = blake2s_simd::Params::new();
+                blake2s_simd::benchmarks::force_portable(&mut params);
+                params.hash(b).as_bytes().to_vec()
+            })
+        ),
+        ( 
+            "blake2s-simd-portable", "BLAKE2sp",
+            Box::new(|b| {
+                let mut params = blake2s_simd::blake2sp::Params::new();
+                blake2s_simd::benchmarks::force_portable_blake2sp(&mut params);
+                params.hash(b).as_bytes().to_vec()
+            })
+        ),
+
         // blake3
 
         ( "blake3", "BLAKE3",  Box::new(|b| blake3::hash(b).as_bytes().to_vec()) ),
@@ -205,26 +332,26 @@ fn hashes() -> Vec<(&'static str, &'static str, Box<dyn Fn(&[u8]) -> Vec<u8> + S
 
         // multihash
 
-        ( "multihash", "SHA-1",       Box::new(|b| multihash::Sha1::digest(b).to_vec()) ),
-        ( "multihash", "SHA-256",     Box::new(|b| multihash::Sha2_256::digest(b).to_vec()) ),
-        ( "multihash", "SHA-512",     Box::new(|b| multihash::Sha2_512::digest(b).to_vec()) ),
-        ( "multihash", "SHA3-256",   Box::new(|b| multihash::Sha3_256::digest(b).to_vec()) ),
-        ( "multihash", "SHA3-512",   Box::new(|b| multihash::Sha3_512::digest(b).to_vec()) ),       
-        ( "multihash", "Keccak-256", Box::new(|b| multihash::Keccak256::digest(b).to_vec()) ),       
-        ( "multihash", "Keccak-512", Box::new(|b| multihash::Keccak512::digest(b).to_vec()) ),       
-        ( "multihash", "BLAKE2b",    Box::new(|b| multihash::Blake2b512::digest(b).to_vec()) ),       
-        ( "multihash", "BLAKE2s",    Box::new(|b| multihash::Blake2s256::digest(b).to_vec()) ),       
+        ( "multihash", "SHA-1",       Box::new(|b| multihash::Sha1::digest(b).digest().to_vec()) ),
+        ( "multihash", "SHA-256",     Box::new(|b| multihash::Sha2_256::digest(b).digest().to_vec()) ),
+        ( "multihash", "SHA-512",     Box::new(|b| multihash::Sha2_512::digest(b).digest().to_vec()) ),
+        ( "multihash", "SHA3-256",   Box::new(|b| multihash::Sha3_256::digest(b).digest().to_vec()) ),
+        ( "multihash", "SHA3-512",   Box::new(|b| multihash::Sha3_512::digest(b).digest().to_vec()) ),       
+        ( "multihash", "Keccak-256", Box::new(|b| multihash::Keccak256::digest(b).digest().to_vec()) ),       
+        ( "multihash", "Keccak-512", Box::new(|b| multihash::Keccak512::digest(b).digest().to_vec()) ),       
+        ( "multihash", "BLAKE2b",    Box::new(|b| multihash::Blake2b512::digest(b).digest().to_vec()) ),       
+        ( "multihash", "BLAKE2s",    Box::new(|b| multihash::Blake2s256::digest(b).digest().to_vec()) ),       
 
         // tiny-keccak
-        ( "tiny-keccak", "Keccak-224", Box::new(|b| tiny_keccak_hash(tiny_keccak::Keccak::v224(), b) ) ),
-        ( "tiny-keccak", "Keccak-256", Box::new(|b| tiny_keccak_hash(tiny_keccak::Keccak::v256(), b) ) ),
-        ( "tiny-keccak", "Keccak-384", Box::new(|b| tiny_keccak_hash(tiny_keccak::Keccak::v384(), b) ) ),
-        ( "tiny-keccak", "Keccak-512", Box::new(|b| tiny_keccak_hash(tiny_keccak::Keccak::v512(), b) ) ),
-        ( "tiny-keccak", "SHA3-224", Box::new(|b| tiny_keccak_hash(tiny_keccak::Sha3::v224(), b) ) ),
-        ( "tiny-keccak", "SHA3-256", Box::new(|b| tiny_keccak_hash(tiny_keccak::Sha3::v256(), b) ) ),
-        ( "tiny-keccak", "SHA3-384", Box::new(|b| tiny_keccak_hash(tiny_keccak::Sha3::v384(), b) ) ),
-        ( "tiny-keccak", "SHA3-512", Box::new(|b| tiny_keccak_hash(tiny_keccak::Sha3::v512(), b) ) ),
-        ( "tiny-keccak", "KangarooTwelve", Box::new(|b| tiny_keccak_hash(tiny_keccak::KangarooTwelve::new("string-input"), b) ) ),
+        ( "tiny-keccak", "Keccak-224", Box::new(|b| tiny_keccak_hash(tiny_keccak::Keccak::v224(), b, 28) ) ),
+        ( "tiny-keccak", "Keccak-256", Box::new(|b| tiny_keccak_hash(tiny_keccak::Keccak::v256(), b, 32) ) ),
+        ( "tiny-keccak", "Keccak-384", Box::new(|b| tiny_keccak_hash(tiny_keccak::Keccak::v384(), b, 48) ) ),
+        ( "tiny-keccak", "Keccak-512", Box::new(|b| tiny_keccak_hash(tiny_keccak::Keccak::v512(), b, 64) ) ),
+        ( "tiny-keccak", "SHA3-224", Box::new(|b| tiny_keccak_hash(tiny_keccak::Sha3::v224(), b, 28) ) ),
+        ( "tiny-keccak", "SHA3-256", Box::new(|b| tiny_keccak_hash(tiny_keccak::Sha3::v256(), b, 32) ) ),
+        ( "tiny-keccak", "SHA3-384", Box::new(|b| tiny_keccak_hash(tiny_keccak::Sha3::v384(), b, 48) ) ),
+        ( "tiny-keccak", "SHA3-512", Box::new(|b| tiny_keccak_hash(tiny_keccak::Sha3::v512(), b, 64) ) ),
+        ( "tiny-keccak", "KangarooTwelve", Box::new(|b| tiny_keccak_hash(tiny_keccak::KangarooTwelve::new("string-input"), b, 32) ) ),
 
         // ring
 
@@ -260,22 +387,128 @@ fn hashes() -> Vec<(&'static str, &'static str, Box<dyn Fn(&[u8]) -> Vec<u8> + S
                 hasher.write(b);
                 u64_to_vec(hasher.finish())
             })
-        )
+        ),
+
+        // hash table hashers, the -hasher implementations go through `std::hash::Hasher`
+
+        // wyhash
+        ( "wyhash", "wyhash", Box::new(|b| u64_to_vec(wyhash::wyhash(b, 0))) ),
+        ( "wyhash-hasher", "wyhash", Box::new(|b| std_hasher(wyhash::WyHash::with_seed(0), b)) ),
+
+        // rapidhash
+        ( "rapidhash", "rapidhash-v1", Box::new(|b| u64_to_vec(rapidhash::v1::rapidhash_v1(b))) ),
+
+        // komihash
+        ( "komihash", "komihash", Box::new(|b| u64_to_vec(komihash::komihash(b, 0))) ),
+        ( "komihash-hasher", "komihash", Box::new(|b| std_hasher(komihash::KomiHasher::new(0), b)) ),
+
+        // metrohash only comes as a hasher
+        ( "metrohash", "MetroHash-64", Box::new(|b| std_hasher(metrohash::MetroHash64::with_seed(0), b)) ),
+        (
+            "metrohash", "MetroHash-128",
+            Box::new(|b| {
+                let mut hasher = metrohash::MetroHash128::with_seed(0);
+                hasher.write(b);
+                let (h1, h2) = hasher.finish128();
+                [u64_to_vec(h1), u64_to_vec(h2)].concat()
+            })
+        ),
+
+        // cityhash-rs
+        ( "cityhash-rs", "CityHash-128", Box::new(|b| u128_to_vec(cityhash_rs::cityhash_110_128(b))) ),
+
+        // farmhash, the hasher buffers all input and hashes it on finish
+        ( "farmhash", "FarmHash-32", Box::new(|b| u32_to_vec(farmhash::hash32(b))) ),
+        ( "farmhash", "FarmHash-64", Box::new(|b| u64_to_vec(farmhash::hash64(b))) ),
+        ( "farmhash-hasher", "FarmHash-64", Box::new(|b| std_hasher(farmhash::FarmHasher::default(), b)) ),
+
+        // murmur3
+        ( "murmur3", "MurmurHash3-32", Box::new(|b| u32_to_vec(murmur3::murmur3_32(&mut std::io::Cursor::new(b), 0).unwrap())) ),
+        ( "murmur3", "MurmurHash3-x86-128", Box::new(|b| u128_to_vec(murmur3::murmur3_x86_128(&mut std::io::Cursor::new(b), 0).unwrap())) ),
+        ( "murmur3", "MurmurHash3-x64-128", Box::new(|b| u128_to_vec(murmur3::murmur3_x64_128(&mut std::io::Cursor::new(b), 0).unwrap())) ),
+
+        // fnv only comes as a hasher
+        ( "fnv", "FNV-1a-64", Box::new(|b| std_hasher(fnv::FnvHasher::default(), b)) ),
+
+        // crc32fast, the baseline variant skips the PCLMULQDQ implementation
+
+        ( "crc32fast", "CRC-32", Box::new(|b| u32_to_vec(crc32fast::hash(b))) ),
+        ( 
+            "crc32fast-baseline", "CRC-32",
+            Box::new(|b| {
+                let mut hasher = crc32fast::Hasher::internal_new_baseline(0, 0);
+                hasher.update(b);
+                u32_to_vec(hasher.finalize())
+            })
+        ),
+
+        // crc32c uses the SSE 4.2 or ARMv8 CRC instructions when available
+
+        ( "crc32c", "CRC-32C", Box::new(|b| u32_to_vec(crc32c::crc32c(b))) ),
+
+        // crc64fast-nvme, the table variant skips the PCLMULQDQ implementation
+
+        ( 
+            "crc64fast-nvme", "CRC-64/NVME",
+            Box::new(|b| {
+                let mut digest = crc64fast_nvme::Digest::new();
+                digest.write(b);
+                u64_to_vec(digest.sum64())
+            })
+        ),
+        ( 
+            "crc64fast-nvme-table", "CRC-64/NVME",
+            Box::new(|b| {
+                let mut digest = crc64fast_nvme::Digest::new_table();
+                digest.write(b);
+                u64_to_vec(digest.sum64())
+            })
+        ),
+
+        // crc, table-driven without SIMD
+
+        ( "crc", "CRC-32", Box::new(|b| u32_to_vec(CRC_32.checksum(b))) ),
+        ( "crc", "CRC-32C", Box::new(|b| u32_to_vec(CRC_32C.checksum(b))) ),
+        ( "crc", "CRC-64/ECMA-182", Box::new(|b| u64_to_vec(CRC_64_ECMA_182.checksum(b))) ),
+        ( "crc", "CRC-64/XZ", Box::new(|b| u64_to_vec(CRC_64_XZ.checksum(b))) ),
+        ( "crc", "CRC-64/NVME", Box::new(|b| u64_to_vec(CRC_64_NVME.checksum(b))) ),
+
+        // adler and simd-adler32
+
+        ( "adler", "Adler-32", Box::new(|b| u32_to_vec(adler::adler32_slice(b))) ),
+        ( 
+            "simd-adler32", "Adler-32",
+            Box::new(|b| {
+                let mut hasher = simd_adler32::Adler32::new();
+                hasher.write(b);
+                u32_to_vec(hasher.finish())
+            })
+        ),
     ]
 }
 
 arg_enum! {
 #[derive(StructOpt, Copy, Clone, PartialEq, Debug)]
-enum Format {
+pub enum Format {
     Text,
     Csv,
+    Json,
+    Markdown,
+}
 }
+
+/// Parses a non-zero size in megabytes, as throughput is measured over hashing that much
+fn parse_size(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>().map_err(|err| err.to_string())? {
+        0 => Err("size must be at least 1 MB".to_string()),
+        size => Ok(size),
+    }
 }
 
 #[derive(StructOpt)]
 struct Options {
     /// Size in megabytes to hash
-    #[structopt(long, default_value = "20")]
+    #[structopt(long, default_value = "20", parse(try_from_str = parse_size))]
     size: usize,
 
     /// Only run hashes with a name that matches the filter string
@@ -286,6 +519,40 @@ struct Options {
     #[structopt(long)]
     show_hashes: bool,
 
+    /// Encoding of the hash output shown with --show-hashes, all but hex have a multibase prefix and
+    /// multihash also prefixes the multicodec code
+    #[structopt(long, default_value = "Base58", possible_values = &encoding::Encoding::variants(), case_insensitive = true)]
+    encoding: encoding::Encoding,
+
+    /// Cache state before each timed run, warm or cold where the last-level cache is evicted
+    /// first. Give both, as in warm,cold, to report them side by side
+    #[structopt(long, default_value = "warm", require_delimiter = true, possible_values = &["warm", "cold"], case_insensitive = true)]
+    cache: Vec<cache::CacheMode>,
+
+    /// Record cycles, instructions, cache misses and branch misses of the single-threaded runs
+    /// with Linux perf_event_open hardware counters
+    #[structopt(long)]
+    counters: bool,
+
+    /// Directory to write a flamegraph SVG and a pprof protobuf of each hash to, sampled while
+    /// repeating the hash after the timed runs
+    #[structopt(long, parse(from_os_str))]
+    profile: Option<std::path::PathBuf>,
+
+    /// Write an HTML report with charts of the single-threaded throughput to this file
+    #[structopt(long, parse(from_os_str))]
+    html: Option<std::path::PathBuf>,
+
+    /// Also measure the single-threaded throughput at these input sizes in KiB, up to --size,
+    /// charted by input size in the HTML report
+    #[structopt(long, require_delimiter = true, requires = "html")]
+    sweep: Vec<usize>,
+
+    /// Append the run, with its git commit, rustc version, crate versions, CPU model, target and
+    /// features, to this SQLite database, see the history subcommand
+    #[structopt(long, parse(from_os_str))]
+    history: Option<std::path::PathBuf>,
+
     // Number of threads to test with
     #[structopt(long)]
     threads: Option<usize>,
@@ -294,6 +561,9 @@ struct Options {
     #[structopt(long, default_value = "Text", possible_values = &Format::variants(), case_insensitive = true)]
     format: Format,
 
+    #[structopt(flatten)]
+    selection: registry::Selection,
+
     #[structopt(subcommand)]
     cmd: Option<Command>,
 }
@@ -301,92 +571,380 @@ struct Options {
 #[derive(StructOpt)]
 enum Command {
     /// List supported hash types
-    ListHashes,
+    ListHashes {
+        /// Show every implementation with its metadata
+        #[structopt(long)]
+        verbose: bool,
+    },
+
+    /// Benchmark HashMap/HashSet workloads with each hasher used as a BuildHasher
+    HashMap {
+        /// Number of keys to insert, look up and remove
+        #[structopt(long, default_value = "1000000")]
+        keys: usize,
+
+        /// Use a HashSet instead of a HashMap
+        #[structopt(long)]
+        set: bool,
+    },
+
+    /// Verify keyed hashes and MACs against test vectors and benchmark them
+    Mac,
+
+    /// Benchmark extendable-output functions, measuring absorb and squeeze separately
+    Xof {
+        /// Number of output bytes to squeeze
+        #[structopt(long, default_value = "16777216")]
+        output_len: usize,
+
+        /// Customization string for cSHAKE, KangarooTwelve, ParallelHash and TupleHash
+        #[structopt(long, default_value = "")]
+        customization: String,
+    },
+
+    /// Hash files and directories, or verify them against a manifest with --check
+    HashFiles(hash_files::Options),
+
+    /// Benchmark bao encoding, slice extraction, slice verification and decoding
+    Bao,
+
+    /// Benchmark password hashes and KDFs across grids of cost parameters, and suggest the
+    /// strongest parameters within a target latency
+    Kdf(kdf::Options),
+
+    /// Benchmark content-defined chunking with FastCDC, Gear, Rabin and Buzhash, reporting the
+    /// chunk-size distribution and the dedup ratio against an earlier version of the input
+    Chunking(chunking::Options),
+
+    /// Benchmark each hash over slices starting at every offset within a cache line, from heap,
+    /// page-aligned or huge-page backed buffers
+    Alignment(alignment::Options),
+
+    /// Decode multihashes, as shown with --encoding multihash, into their algorithm and digest
+    Decode {
+        /// Multibase encoded multihashes
+        #[structopt(required = true)]
+        multihashes: Vec<String>,
+    },
+
+    /// Show the speed of each hash over the runs recorded with --history, flagging step changes
+    /// and what changed in the build between the runs
+    History(history::Options),
 }
 
-fn perf_test(options: Options) {
-    let mut hashes = hashes();
-    hashes.sort_by(|(_, hash1, _), (_, hash2, _)| {
-        hash1.to_ascii_lowercase().cmp(&hash2.to_ascii_lowercase())
-    });
-
-    let threads = options.threads.unwrap_or_else(num_cpus::get);
+/// Sets up the global rayon thread pool, returning the number of threads used
+fn init_thread_pool(threads: Option<usize>) -> usize {
+    let threads = threads.unwrap_or_else(num_cpus::get);
     rayon::ThreadPoolBuilder::new()
         .num_threads(threads)
         .build_global()
         .unwrap();
+    threads
+}
 
-    let bytes = vec![0u8; options.size * 1024 * 1024];
+#[derive(serde::Serialize)]
+struct HashResult<'a> {
+    implementation: &'a str,
+    hash: &'a str,
+    family: &'a str,
+    category: registry::Category,
+    output_bits: usize,
+    backend: &'a str,
+    digest: String,
+    cache: String,
+    mb_per_s: f64,
+    mt_mb_per_s: f64,
+    mt_ratio: f64,
+    #[serde(flatten)]
+    counters: Option<counters::CounterResult>,
+}
+
+fn perf_test(options: &Options, command: &'static str, mut hashes: Vec<registry::HashEntry>) {
+    hashes.sort_by(|hash1, hash2| {
+        hash1
+            .name
+            .to_ascii_lowercase()
+            .cmp(&hash2.name.to_ascii_lowercase())
+    });
+
+    let threads = init_thread_pool(options.threads);
+
+    let mut bytes = vec![0u8; options.size * 1024 * 1024];
+    // write to every page so the buffer isn't backed by the shared zero page, which always stays
+    // in cache
+    for page in bytes.chunks_mut(4096) {
+        page[0] = std::hint::black_box(0);
+    }
+
+    // label the speeds with their cache mode, unless only the default warm mode is run
+    let show_mode = options.cache != [cache::CacheMode::Warm];
+    let mut evictor = cache::Evictor::new(&options.cache);
+    let mut counters = counters::open(options.counters);
+    let profiler = profile::open(options.profile.as_deref());
+    let mut charts = options.html.as_ref().map(|_| html::Charts::default());
+    let mut history = history::open(options.history.as_deref(), command);
+
+    let sweep_sizes = options
+        .sweep
+        .iter()
+        .map(|kib| kib * 1024)
+        .collect::<Vec<_>>();
+    if let Some(size) = sweep_sizes.iter().find(|&&size| size > bytes.len()) {
+        eprintln!("sweep size {} KiB is larger than --size", size / 1024);
+        std::process::exit(1);
+    }
+
+    let metadata = report::Metadata::new(command, threads, Some(bytes.len()), "zeros");
+
+    if options.format == Format::Text {
+        println!("CPU features: {}", metadata.cpu_features.join(" "));
+    }
+
+    let mut report = report::Report::new(options.format, metadata);
 
     if options.format == Format::Csv {
-        println!("implementation,hash,MB/s,MT MB/s,MT ratio");
+        print!("implementation,hash,MB/s,MT MB/s,MT ratio,cache,backend");
+        if counters.is_some() {
+            print!(",cycles/B,instructions/B,IPC,cycles,instructions,cache misses,branch misses");
+        }
+        println!();
     }
 
-    for (impl_name, hash_name, hash_func) in &hashes {
+    for entry in &hashes {
         if let Some(filter) = &options.filter {
-            if !impl_name.contains(filter) {
+            if !entry.implementation.contains(filter) {
                 continue; // skip
             }
         }
+        if !options.selection.matches(entry) {
+            continue;
+        }
 
-        let start_time = Instant::now();
-        let hash_result = hash_func(&bytes);
-        let st_duration = start_time.elapsed().as_secs_f64();
+        let (impl_name, hash_name, hash_func) = (entry.implementation, entry.name, &entry.func);
+
+        let mut hash_result = vec![];
+        let speeds = options
+            .cache
+            .iter()
+            .map(|&mode| {
+                evictor.prepare(mode);
+                let start_time = Instant::now();
+                let (result, counts) = counters::measure(&mut counters, || hash_func(&bytes));
+                hash_result = result;
+                let st_duration = start_time.elapsed().as_secs_f64();
+
+                evictor.prepare(mode);
+                let start_time = Instant::now();
+                (0..threads).into_par_iter().for_each(|_i| {
+                    let _ = hash_func(&bytes);
+                });
+                let mt_duration = start_time.elapsed().as_secs_f64() / (threads as f64);
+
+                let st_speed = (bytes.len() as f64) / (1024f64 * 1024f64) / st_duration;
+                let mt_speed = (bytes.len() as f64) / (1024f64 * 1024f64) / mt_duration;
+                (
+                    mode.to_string().to_ascii_lowercase(),
+                    st_speed,
+                    mt_speed,
+                    counts.map(|c| c.per_byte(bytes.len())),
+                )
+            })
+            .collect::<Vec<_>>();
 
-        let start_time = Instant::now();
-        (0..threads).into_par_iter().for_each(|_i| {
+        profile::profile(&profiler, &format!("{}-{}", impl_name, hash_name), || {
             let _ = hash_func(&bytes);
         });
-        let mt_duration = start_time.elapsed().as_secs_f64() / (threads as f64);
 
-        let st_speed = (bytes.len() as f64) / (1024f64 * 1024f64) / st_duration;
-        let mt_speed = (bytes.len() as f64) / (1024f64 * 1024f64) / mt_duration;
+        if let Some(charts) = &mut charts {
+            let name = format!("{} {}", hash_name, impl_name);
+            for (mode, st_speed, ..) in &speeds {
+                charts.add_speed(mode, name.clone(), *st_speed);
+            }
+            if !sweep_sizes.is_empty() {
+                charts.add_sweep(name, sweep(hash_func, &bytes, &sweep_sizes));
+            }
+        }
+
+        if let Some(history) = &mut history {
+            for (mode, st_speed, ..) in &speeds {
+                history.add(
+                    format!("{} {}", hash_name, impl_name),
+                    mode,
+                    "MB/s",
+                    *st_speed,
+                );
+            }
+        }
 
         match options.format {
             Format::Text => {
                 print!(
-                    "{:15} {:13} {:>6.0} MB/s {:>6.0} MB/s {:>5.1}x",
-                    hash_name,
-                    impl_name,
-                    st_speed,
-                    mt_speed,
-                    mt_speed / st_speed
+                    "{:20} {:20} {:19}",
+                    hash_name, impl_name, entry.implementation_info.backend
                 );
 
+                for (mode, st_speed, mt_speed, counters) in &speeds {
+                    if show_mode {
+                        print!(" {}:", mode);
+                    }
+                    print!(
+                        " {:>6.0} MB/s {:>6.0} MB/s {:>5.1}x",
+                        st_speed,
+                        mt_speed,
+                        mt_speed / st_speed
+                    );
+                    if let Some(c) = counters {
+                        print!(
+                            " {:>6.2} cycles/B {:>6.2} instr/B {:>4.2} IPC {:>9} cache misses {:>9} branch misses",
+                            c.cycles_per_byte,
+                            c.instructions_per_byte,
+                            c.ipc,
+                            c.cache_misses,
+                            c.branch_misses
+                        );
+                    }
+                }
+
                 if options.show_hashes {
                     println!(
                         "  {}",
-                        multibase::encode(multibase::Base::Base58Btc, hash_result)
+                        encoding::encode(options.encoding, hash_name, &hash_result)
                     );
                 } else {
                     println!();
                 }
             }
             Format::Csv => {
-                println!(
-                    "{},{},{:.0},{:.0},{}",
-                    impl_name,
-                    hash_name,
-                    st_speed,
-                    mt_speed,
-                    mt_speed / st_speed
-                );
+                for (mode, st_speed, mt_speed, counters) in &speeds {
+                    print!(
+                        "{},{},{:.2},{:.2},{},{},{}",
+                        impl_name,
+                        hash_name,
+                        st_speed,
+                        mt_speed,
+                        mt_speed / st_speed,
+                        mode,
+                        entry.implementation_info.backend
+                    );
+                    if let Some(c) = counters {
+                        print!(
+                            ",{:.3},{:.3},{:.3},{},{},{},{}",
+                            c.cycles_per_byte,
+                            c.instructions_per_byte,
+                            c.ipc,
+                            c.cycles,
+                            c.instructions,
+                            c.cache_misses,
+                            c.branch_misses
+                        );
+                    }
+                    println!();
+                }
+            }
+            Format::Json | Format::Markdown => {
+                for (mode, st_speed, mt_speed, counters) in speeds {
+                    report.add(HashResult {
+                        implementation: impl_name,
+                        hash: hash_name,
+                        family: entry.algorithm.family,
+                        category: entry.algorithm.category,
+                        output_bits: entry.output_bits,
+                        backend: entry.implementation_info.backend,
+                        digest: hex::encode(&hash_result),
+                        cache: mode,
+                        mb_per_s: st_speed,
+                        mt_mb_per_s: mt_speed,
+                        mt_ratio: mt_speed / st_speed,
+                        counters,
+                    });
+                }
             }
         }
     }
+
+    report.finish();
+
+    if let (Some(charts), Some(path)) = (&charts, &options.html) {
+        charts.write(path);
+    }
+    if let Some(history) = history {
+        history.write();
+    }
 }
 
-fn list_hashes() {
-    let mut hash_names = hashes()
-        .into_iter()
-        .map(|(_, hash_name, _)| hash_name.to_string())
-        .collect::<HashSet<_>>()
+/// Minimum time each input size of a sweep is hashed for, repeating small inputs
+const SWEEP_DURATION: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// Single-threaded MB/s at each input size, hashing the start of the buffer
+fn sweep(hash_func: &HashFn, bytes: &[u8], sizes: &[usize]) -> Vec<(usize, f64)> {
+    sizes
+        .iter()
+        .map(|&size| {
+            let input = &bytes[..size];
+            let start_time = Instant::now();
+            let mut iterations = 0;
+            while start_time.elapsed() < SWEEP_DURATION {
+                let _ = hash_func(input);
+                iterations += 1;
+            }
+            let mb = (size * iterations) as f64 / (1024f64 * 1024f64);
+            (size, mb / start_time.elapsed().as_secs_f64())
+        })
+        .collect()
+}
+
+fn list_hashes(options: &Options, verbose: bool) {
+    let mut hashes = registry::registry(hashes())
         .into_iter()
+        .filter(|entry| options.selection.matches(entry))
         .collect::<Vec<_>>();
-    hash_names.sort();
 
-    for hash_name in hash_names {
-        println!("{}", hash_name);
+    if !verbose {
+        let mut hash_names = hashes
+            .into_iter()
+            .map(|entry| entry.name.to_string())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        hash_names.sort();
+
+        for hash_name in hash_names {
+            println!("{}", hash_name);
+        }
+        return;
+    }
+
+    hashes.sort_by(|hash1, hash2| {
+        (hash1.name.to_ascii_lowercase(), hash1.implementation)
+            .cmp(&(hash2.name.to_ascii_lowercase(), hash2.implementation))
+    });
+
+    println!(
+        "{:20} {:20} {:12} {:>5} {:9} {:5} {:5} {:9} {:10} targets",
+        "hash",
+        "implementation",
+        "family",
+        "bits",
+        "category",
+        "keyed",
+        "tree",
+        "pure rust",
+        "backend"
+    );
+    for entry in &hashes {
+        println!(
+            "{:20} {:20} {:12} {:>5} {:9} {:5} {:5} {:9} {:10} {}",
+            entry.name,
+            entry.implementation,
+            entry.algorithm.family,
+            entry.output_bits,
+            entry.algorithm.category.to_string(),
+            entry.algorithm.keyed,
+            entry.algorithm.tree,
+            entry.implementation_info.pure_rust,
+            entry.implementation_info.backend,
+            entry.implementation_info.targets
+        );
     }
 }
 
@@ -394,7 +952,68 @@ fn main() {
     let options = Options::from_args();
 
     match options.cmd {
-        Some(Command::ListHashes) => list_hashes(),
-        None => perf_test(options),
+        Some(Command::ListHashes { verbose }) => list_hashes(&options, verbose),
+        Some(Command::HashMap { keys, set }) => {
+            hashmap::run(options.format, options.filter.as_deref(), keys, set)
+        }
+        Some(Command::Mac) => {
+            mac::verify();
+            perf_test(&options, "mac", registry::registry(mac::macs()));
+        }
+        Some(Command::Xof {
+            output_len,
+            ref customization,
+        }) => {
+            xof::verify();
+            xof::run(&xof::Options {
+                format: options.format,
+                filter: options.filter.as_deref(),
+                show_hashes: options.show_hashes,
+                encoding: options.encoding,
+                size: options.size,
+                output_len,
+                customization,
+            });
+        }
+        Some(Command::HashFiles(ref hash_files_options)) => {
+            init_thread_pool(options.threads);
+            hash_files::run(hash_files_options);
+        }
+        Some(Command::Bao) => bao_stream::run(options.format, options.size),
+        Some(Command::Kdf(ref kdf_options)) => {
+            let threads = init_thread_pool(options.threads);
+            kdf::verify();
+            kdf::run(
+                kdf_options,
+                options.format,
+                options.filter.as_deref(),
+                threads,
+            );
+        }
+        Some(Command::Chunking(ref chunking_options)) => {
+            chunking::run(chunking_options, options.format, options.filter.as_deref())
+        }
+        Some(Command::Alignment(ref alignment_options)) => {
+            let hashes = registry::registry(hashes())
+                .into_iter()
+                .filter(|entry| options.selection.matches(entry))
+                .collect();
+            alignment::run(
+                alignment_options,
+                options.format,
+                options.filter.as_deref(),
+                options.size,
+                hashes,
+            );
+        }
+        Some(Command::Decode { ref multihashes }) => encoding::run(multihashes),
+        Some(Command::History(ref history_options)) => {
+            history::run(history_options, options.filter.as_deref())
+        }
+        None => {
+            let hashes = registry::registry(hashes());
+            vectors::verify(&hashes);
+            perf_test(&options, "hash", hashes);
+        }
     }
 }
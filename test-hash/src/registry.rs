@@ -0,0 +1,205 @@
+//! Metadata for the registered hashes and selection of hashes by that metadata
+
+use structopt::{clap::arg_enum, StructOpt};
+
+use crate::HashFn;
+
+arg_enum! {
+#[derive(Copy, Clone, PartialEq, Debug, serde::Serialize)]
+pub enum Category {
+    Crypto,
+    NonCrypto,
+    Checksum,
+}
+}
+
+/// Properties of a hash algorithm, shared by all of its implementations
+pub struct AlgorithmInfo {
+    pub family: &'static str,
+    pub category: Category,
+    /// Takes a key or seed
+    pub keyed: bool,
+    /// Parallel or tree-based construction
+    pub tree: bool,
+}
+
+/// Properties of a crate implementing one or more hash algorithms
+pub struct ImplementationInfo {
+    /// Implemented entirely in Rust, as opposed to using C or assembly
+    pub pure_rust: bool,
+    /// Targets the implementation is registered for
+    pub targets: &'static str,
+    /// SIMD backend the implementation runs with on this CPU
+    pub backend: &'static str,
+}
+
+pub struct HashEntry {
+    pub implementation: &'static str,
+    pub name: &'static str,
+    pub func: HashFn,
+    pub output_bits: usize,
+    pub algorithm: AlgorithmInfo,
+    pub implementation_info: ImplementationInfo,
+}
+
+#[rustfmt::skip]
+fn algorithm_info(name: &str) -> AlgorithmInfo {
+    use Category::*;
+
+    let (family, category, keyed, tree) = match name {
+        "XXH-32" | "XXH-64" | "XXH3-64" | "XXH3-64-seeded" | "XXH3-64-secret"
+        | "XXH3-128" | "XXH3-128-seeded" | "XXH3-128-secret" => ("xxHash", NonCrypto, true, false),
+        "MeowHash" => ("MeowHash", NonCrypto, false, false),
+        "SeaHash" => ("SeaHash", NonCrypto, true, false),
+        "t1ha0" | "t1ha1" => ("t1ha", NonCrypto, true, false),
+        "aHash" => ("aHash", NonCrypto, true, false),
+        "wyhash" => ("wyhash", NonCrypto, true, false),
+        "rapidhash-v1" => ("rapidhash", NonCrypto, true, false),
+        "komihash" => ("komihash", NonCrypto, true, false),
+        "MetroHash-64" | "MetroHash-128" => ("MetroHash", NonCrypto, true, false),
+        "CityHash-128" => ("CityHash", NonCrypto, true, false),
+        "FarmHash-32" | "FarmHash-64" => ("FarmHash", NonCrypto, true, false),
+        "MurmurHash3-32" | "MurmurHash3-x86-128" | "MurmurHash3-x64-128" => ("MurmurHash3", NonCrypto, true, false),
+        "FNV-1a-64" => ("FNV", NonCrypto, false, false),
+        "STHash" => ("STHash", Crypto, true, false),
+        "MD5" => ("MD5", Crypto, false, false),
+        "SHA-1" => ("SHA-1", Crypto, false, false),
+        "SHA-224" | "SHA-256" | "SHA-384" | "SHA-512" | "SHA-512-224" | "SHA-512-256" => ("SHA-2", Crypto, false, false),
+        "SHA3-224" | "SHA3-256" | "SHA3-384" | "SHA3-512" => ("SHA-3", Crypto, false, false),
+        "Keccak256" | "Keccak384" | "Keccak512"
+        | "Keccak-224" | "Keccak-256" | "Keccak-384" | "Keccak-512" => ("Keccak", Crypto, false, false),
+        "KangarooTwelve" => ("Keccak", Crypto, false, true),
+        "RIPEMD-160" => ("RIPEMD", Crypto, false, false),
+        "SM3" => ("SM3", Crypto, false, false),
+        "Whirlpool" => ("Whirlpool", Crypto, false, false),
+        "Streebog-256" | "Streebog-512" => ("Streebog", Crypto, false, false),
+        "Groestl-256" | "Groestl-512" => ("Groestl", Crypto, false, false),
+        "Skein-256" | "Skein-512" => ("Skein", Crypto, false, false),
+        "Tiger" => ("Tiger", Crypto, false, false),
+        "SipHash-1-3" | "SipHash-2-4" | "SipHash-1-3-128" | "SipHash-2-4-128" => ("SipHash", NonCrypto, true, false),
+        "HighwayHash" | "HighwayHash-128" | "HighwayHash-256" => ("HighwayHash", NonCrypto, true, false),
+        "BLAKE2b" | "BLAKE2b-256" | "BLAKE2s" => ("BLAKE2", Crypto, true, false),
+        "BLAKE2bp" | "BLAKE2bp-256" | "BLAKE2sp" => ("BLAKE2", Crypto, true, true),
+        "BLAKE3" | "bao-combined" | "bao-outboard" => ("BLAKE3", Crypto, true, true),
+
+        // checksums
+        "CRC-32" | "CRC-32C" | "CRC-64/ECMA-182" | "CRC-64/XZ" | "CRC-64/NVME" => ("CRC", Checksum, false, false),
+        "Adler-32" => ("Adler-32", Checksum, false, false),
+
+        // keyed hashes and MACs
+        "HMAC-SHA256" | "HMAC-SHA512" => ("HMAC", Crypto, true, false),
+        "BLAKE2b-keyed" | "BLAKE2s-keyed" => ("BLAKE2", Crypto, true, false),
+        "BLAKE3-keyed" => ("BLAKE3", Crypto, true, true),
+        "KMAC128" | "KMAC256" => ("Keccak", Crypto, true, false),
+
+        _ => panic!("no metadata for hash algorithm {}", name),
+    };
+
+    AlgorithmInfo { family, category, keyed, tree }
+}
+
+#[rustfmt::skip]
+fn implementation_info(implementation: &str) -> ImplementationInfo {
+    let (pure_rust, targets) = match implementation {
+        "twox-hash" | "xxhash-rust" | "seahash" | "t1ha" | "md5" | "md-5" | "sha2" | "sha3" | "siphasher"
+        | "highway" | "blake2b" | "blake2s" | "blake2b-simd" | "blake2s-simd" | "multihash"
+        | "tiny-keccak" | "sthash" | "ahash" | "hmac" | "ripemd160" | "sm3" | "whirlpool" | "streebog"
+        | "groestl" | "skein-hash" | "tiger" | "crc32fast" | "crc32fast-baseline" | "crc32c"
+        | "crc64fast-nvme" | "crc64fast-nvme-table" | "crc" | "adler" | "simd-adler32" | "wyhash"
+        | "wyhash-hasher" | "rapidhash" | "komihash" | "komihash-hasher" | "metrohash" | "cityhash-rs" | "farmhash"
+        | "farmhash-hasher" | "murmur3" | "fnv" | "blake2b-simd-portable" | "blake2s-simd-portable"
+        | "highway-portable" => (true, "all"),
+        "meowhash" => (true, "x86_64"),
+        // blake3 uses C and assembly SIMD implementations on x86_64 and aarch64
+        "blake3" | "blake3-rayon" | "bao" => (false, "all"),
+        "ring" => (false, "not wasm32"),
+
+        _ => panic!("no metadata for hash implementation {}", implementation),
+    };
+
+    ImplementationInfo { pure_rust, targets, backend: crate::simd::backend(implementation) }
+}
+
+/// Attaches the metadata to a list of `(implementation, name, function)` hashes
+pub fn registry(hashes: Vec<(&'static str, &'static str, HashFn)>) -> Vec<HashEntry> {
+    hashes
+        .into_iter()
+        .map(|(implementation, name, func)| HashEntry {
+            implementation,
+            name,
+            output_bits: func(&[]).len() * 8,
+            func,
+            algorithm: algorithm_info(name),
+            implementation_info: implementation_info(implementation),
+        })
+        .collect()
+}
+
+/// Selects hashes by their metadata
+#[derive(StructOpt)]
+pub struct Selection {
+    /// Only run hashes of this category
+    #[structopt(long, possible_values = &Category::variants(), case_insensitive = true)]
+    category: Option<Category>,
+
+    /// Only run hashes of this algorithm family, such as SHA-2 or BLAKE2
+    #[structopt(long)]
+    family: Option<String>,
+
+    /// Only run hashes with at least this many output bits
+    #[structopt(long)]
+    min_bits: Option<usize>,
+
+    /// Only run hashes with at most this many output bits
+    #[structopt(long)]
+    max_bits: Option<usize>,
+
+    /// Only run keyed or seedable hashes
+    #[structopt(long)]
+    keyed: bool,
+
+    /// Only run parallel or tree-based hashes
+    #[structopt(long)]
+    tree: bool,
+
+    /// Only run implementations written entirely in Rust
+    #[structopt(long)]
+    pure_rust: bool,
+
+    /// Skip implementations with this name, can be repeated
+    #[structopt(long, number_of_values = 1)]
+    exclude_impl: Vec<String>,
+
+    /// Only run implementations on portable or scalar backends, such as blake2b-simd-portable or
+    /// blake3 built with the blake3-portable feature, to compare against their SIMD backends
+    #[structopt(long)]
+    portable: bool,
+}
+
+impl Selection {
+    pub fn matches(&self, entry: &HashEntry) -> bool {
+        self.category.iter().all(|c| *c == entry.algorithm.category)
+            && self
+                .family
+                .iter()
+                .all(|f| f.eq_ignore_ascii_case(entry.algorithm.family))
+            && self.min_bits.iter().all(|b| entry.output_bits >= *b)
+            && self.max_bits.iter().all(|b| entry.output_bits <= *b)
+            && (!self.keyed || entry.algorithm.keyed)
+            && (!self.tree || entry.algorithm.tree)
+            && (!self.pure_rust || entry.implementation_info.pure_rust)
+            && !self.exclude_impl.iter().any(|i| i == entry.implementation)
+            && (!self.portable || crate::simd::is_portable(entry.implementation_info.backend))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    /// `algorithm_info` and `implementation_info` panic on names they don't know about, so
+    /// every registered hash and MAC needs an entry there
+    #[test]
+    fn every_hash_has_metadata() {
+        super::registry(crate::hashes());
+        super::registry(crate::mac::macs());
+    }
+}
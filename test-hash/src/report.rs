@@ -0,0 +1,134 @@
+//! Structured JSON and Markdown output, with metadata about the run
+
+use serde::Serialize;
+use serde_json::Value;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::Format;
+
+/// Describes the run so results from different platforms and runtimes can be merged
+#[derive(Serialize)]
+pub struct Metadata {
+    pub tool: &'static str,
+    pub version: &'static str,
+    pub command: &'static str,
+    pub target: &'static str,
+    pub os: &'static str,
+    pub arch: &'static str,
+    pub cpu_features: Vec<&'static str>,
+    pub threads: usize,
+    pub size_bytes: Option<usize>,
+    pub input: &'static str,
+    pub timestamp: u64,
+}
+
+impl Metadata {
+    pub fn new(
+        command: &'static str,
+        threads: usize,
+        size_bytes: Option<usize>,
+        input: &'static str,
+    ) -> Self {
+        Self {
+            tool: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+            command,
+            target: env!("TARGET"),
+            os: std::env::consts::OS,
+            arch: std::env::consts::ARCH,
+            cpu_features: crate::simd::cpu_features(),
+            threads,
+            size_bytes,
+            input,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        }
+    }
+}
+
+/// Collects result rows for the structured formats, `Text` and `Csv` are printed directly
+pub struct Report {
+    format: Format,
+    metadata: Metadata,
+    results: Vec<Value>,
+}
+
+impl Report {
+    pub fn new(format: Format, metadata: Metadata) -> Self {
+        Self {
+            format,
+            metadata,
+            results: vec![],
+        }
+    }
+
+    pub fn add(&mut self, result: impl Serialize) {
+        self.results.push(serde_json::to_value(result).unwrap());
+    }
+
+    pub fn finish(self) {
+        match self.format {
+            Format::Text | Format::Csv => {}
+            Format::Json => {
+                let report = serde_json::json!({
+                    "metadata": self.metadata,
+                    "results": self.results,
+                });
+                println!("{}", serde_json::to_string_pretty(&report).unwrap());
+            }
+            Format::Markdown => self.print_markdown(),
+        }
+    }
+
+    fn print_markdown(&self) {
+        let metadata = serde_json::to_value(&self.metadata).unwrap();
+        for (key, value) in metadata.as_object().unwrap() {
+            println!("- **{}**: {}", key, markdown_value(value));
+        }
+        println!();
+
+        let columns = match self.results.first().and_then(Value::as_object) {
+            Some(first) => first.keys().collect::<Vec<_>>(),
+            None => return,
+        };
+
+        println!(
+            "| {} |",
+            columns
+                .iter()
+                .map(|c| c.as_str())
+                .collect::<Vec<_>>()
+                .join(" | ")
+        );
+        println!("|{}", " --- |".repeat(columns.len()));
+
+        for result in &self.results {
+            let cells = columns
+                .iter()
+                .map(|c| markdown_value(&result[c.as_str()]))
+                .collect::<Vec<_>>();
+            println!("| {} |", cells.join(" | "));
+        }
+    }
+}
+
+fn markdown_value(value: &Value) -> String {
+    match value {
+        Value::Null => "-".to_string(),
+        Value::String(s) => s.clone(),
+        Value::Array(values) => values
+            .iter()
+            .map(markdown_value)
+            .collect::<Vec<_>>()
+            .join(", "),
+        Value::Number(n) => match (n.as_u64(), n.as_f64()) {
+            (Some(v), _) => v.to_string(),
+            (None, Some(v)) if v.abs() >= 100.0 => format!("{:.0}", v),
+            (None, Some(v)) => format!("{:.2}", v),
+            (None, None) => n.to_string(),
+        },
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,111 @@
+//! Detected CPU features and the SIMD backend each implementation picks with them
+
+pub use bench_common::simd::cpu_features;
+
+/// blake2b-simd and blake2s-simd don't report the implementation they dispatch to, it is
+/// inferred from the CPU features they check for
+fn blake2_simd_backend() -> &'static str {
+    let features = cpu_features();
+
+    if features.contains(&"avx2") {
+        "avx2 (inferred)"
+    } else if features.contains(&"sse4.1") {
+        "sse4.1 (inferred)"
+    } else {
+        "portable (inferred)"
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn highway_backend() -> &'static str {
+    if highway::AvxHash::new(highway::Key::default()).is_some() {
+        "avx2"
+    } else if highway::SseHash::new(highway::Key::default()).is_some() {
+        "sse4.1"
+    } else {
+        "portable"
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn highway_backend() -> &'static str {
+    "portable"
+}
+
+/// simd-adler32 detects x86 features at runtime, but WebAssembly SIMD has to be enabled when
+/// compiling with `-C target-feature=+simd128`
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn simd_adler32_backend() -> &'static str {
+    if is_x86_feature_detected!("avx2") {
+        "avx2"
+    } else if is_x86_feature_detected!("ssse3") {
+        "ssse3"
+    } else if is_x86_feature_detected!("sse2") {
+        "sse2"
+    } else {
+        "scalar"
+    }
+}
+
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+fn simd_adler32_backend() -> &'static str {
+    if cfg!(all(target_arch = "wasm32", target_feature = "simd128")) {
+        "simd128"
+    } else {
+        "scalar"
+    }
+}
+
+/// BLAKE3 doesn't report the platform it dispatches to either, it is inferred from the CPU
+/// features it checks for. The `blake3-portable` feature builds it without the others
+fn blake3_backend() -> &'static str {
+    let features = cpu_features();
+
+    if cfg!(feature = "blake3-portable") {
+        "portable"
+    } else if features.contains(&"avx512f") && features.contains(&"avx512vl") {
+        "avx512 (inferred)"
+    } else if features.contains(&"avx2") {
+        "avx2 (inferred)"
+    } else if features.contains(&"sse4.1") {
+        "sse4.1 (inferred)"
+    } else if features.contains(&"sse2") {
+        "sse2 (inferred)"
+    } else if features.contains(&"neon") {
+        "neon (inferred)"
+    } else {
+        "portable (inferred)"
+    }
+}
+
+/// Whether a backend runs without SIMD or crypto instructions
+pub fn is_portable(backend: &str) -> bool {
+    matches!(
+        backend.trim_end_matches(" (inferred)"),
+        "portable" | "fallback" | "table" | "scalar"
+    )
+}
+
+/// The backend an implementation runs with on this CPU, or `-` where it isn't known
+pub fn backend(implementation: &str) -> &'static str {
+    match implementation {
+        "blake3" | "blake3-rayon" | "bao" => blake3_backend(),
+        "blake2b-simd" | "blake2s-simd" => blake2_simd_backend(),
+        "highway" => highway_backend(),
+        "blake2b-simd-portable" | "blake2s-simd-portable" | "highway-portable" => "portable",
+        "simd-adler32" => simd_adler32_backend(),
+        "meowhash" => "aes-ni",
+        // ahash only uses AES-NI if it's enabled when compiling, as with -C target-feature=+aes
+        "ahash"
+            if cfg!(all(
+                any(target_arch = "x86", target_arch = "x86_64"),
+                target_feature = "aes"
+            )) =>
+        {
+            "aes-ni"
+        }
+        "ahash" => "fallback",
+        "crc32fast-baseline" | "crc64fast-nvme-table" => "table",
+        _ => "-",
+    }
+}
@@ -0,0 +1,294 @@
+//! Reference test vectors for the registered hashes
+
+use crate::registry::HashEntry;
+use crate::{u128_to_vec, u32_to_vec, u64_to_vec};
+
+const PRIME32: u64 = 2654435761;
+const PRIME64: u64 = 11400714785074694797;
+
+/// Seeds used by the xxHash sanity tests, which use a different seed for the 64-bit and 128-bit XXH3
+pub const XXH3_64_SEED: u64 = PRIME64;
+pub const XXH3_128_SEED: u64 = PRIME32;
+
+/// Input of the xxHash sanity tests, longer than the largest vector below
+fn sanity_buffer() -> Vec<u8> {
+    let mut byte_gen = PRIME32;
+
+    (0..4096)
+        .map(|_| {
+            let byte = (byte_gen >> 56) as u8;
+            byte_gen = byte_gen.wrapping_mul(PRIME64);
+            byte
+        })
+        .collect()
+}
+
+/// Custom XXH3 secret of the xxHash sanity tests: 11 bytes over the 136 byte minimum
+pub fn xxh3_secret() -> Vec<u8> {
+    sanity_buffer()[7..7 + 136 + 11].to_vec()
+}
+
+// (input length, XXH-32, XXH-64) with seed 0
+#[rustfmt::skip]
+const XXH: &[(usize, u32, u64)] = &[
+    (   0, 0x02CC5D05, 0xEF46DB3751D8E999),
+    (   1, 0xCF65B03E, 0xE934A84ADB052768),
+    (  14, 0x1208E7E2, 0x8282DCC4994E35C8),
+    ( 222, 0x5BD11DBD, 0xB641AE8CB691C174),
+];
+
+// (input length, XXH3-64, XXH3-64-seeded, XXH3-64-secret)
+#[rustfmt::skip]
+const XXH3_64: &[(usize, u64, u64, u64)] = &[
+    (   0, 0x2D06800538D394C2, 0xA8A6B918B2F0364A, 0x3559D64878C5C66C),
+    (   1, 0xC44BDFF4074EECDB, 0x032BE332DD766EF8, 0x8A52451418B2DA4D),
+    (   6, 0x27B56A84CD2D7325, 0x84589C116AB59AB9, 0x82C90AB0519369AD),
+    (  12, 0xA713DAF0DFBB77E7, 0xE7303E1B2336DE0E, 0x14631E773B78EC57),
+    (  24, 0xA3FE70BF9D3510EB, 0x850E80FC35BDD690, 0xCDD5542E4A9D9FE8),
+    (  48, 0x397DA259ECBA1F11, 0xADC2CBAA44ACC616, 0x33ABD54D094B2534),
+    (  80, 0xBCDEFBBB2C47C90A, 0xC6DD0CB699532E73, 0xE687BA1684965297),
+    ( 195, 0xCD94217EE362EC3A, 0xBA68003D370CB3D9, 0xA057273F5EECFB20),
+    ( 403, 0xCDEB804D65C6DEA4, 0x6259F6ECFD6443FD, 0x14546019124D43B8),
+    ( 512, 0x617E49599013CB6B, 0x3CE457DE14C27708, 0x7564693DD526E28D),
+    (2048, 0xDD59E2C3A5F038E0, 0x66F81670669ABABC, 0xD32E975821D6519F),
+    (2240, 0x6E73A90539CF2948, 0x757BA8487D1B5247, 0xB26C938C7AF3A71F),
+    (2367, 0xCB37AEB9E5D361ED, 0xD2DB3415B942B42A, 0x293FA8E5173BB5E7),
+];
+
+// (input length, XXH3-128, XXH3-128-seeded, XXH3-128-secret)
+#[rustfmt::skip]
+const XXH3_128: &[(usize, u128, u128, u128)] = &[
+    (   0, 0x99AA06D3014798D86001C324468D497F, 0x92220AE55E14AB505444F7869C671AB0, 0x5F70F4EA232F1D38005923CCEECBE8AE),
+    (   1, 0xA6CD5E9392000F6AC44BDFF4074EECDB, 0x89B99554BA22467CB53D5557E7F76F8D, 0x3A66AF5A9819198E8A52451418B2DA4D),
+    (   6, 0x082AFE0B8162D12A3E7039BDDA43CFC6, 0x5A865B5389ABD2B1269D8F70BE98856E, 0x376BD91B6432F36D0B61C8ACA7D4778F),
+    (  12, 0x6E3EFD8FC7802B18061A192713F69AD9, 0xD7E09D518A3405D39BE9F9A67F3C7DFB, 0x90A3C2D839F57D0FAF82F6EBA263D7D8),
+    (  24, 0x0CE966E4678D37611E7044D28B1B901D, 0x3162026714A6A243D7304C54EBAD40A9, 0x3476C01AB8B8E821D9ED8351E0BB5526),
+    (  48, 0xA002AC4E5478227EF942219AED80F67B, 0x163ADDE36C0722957BA3C3E453A1934E, 0x2C599633A4D78138D3488D14A0FC9147),
+    (  80, 0xFDF2CEFDE9EAAC8A454AE6BF7A8A532D, 0x27F9BE94EF318D637ABFEBDF2326DFF0, 0x5DE1C8EB7BD056B812568D8D732F8544),
+    ( 195, 0x7729543A26B207EE3FB593C086A66075, 0xC45384C3283266176447716EBBD2DA99, 0x18783075F43015BE002CDB4476B9A36F),
+    ( 403, 0x1B6DE21E332DD73DCDEB804D65C6DEA4, 0x1EF41459552CB8391FEF87BD75DBE404, 0xE14EEDF084A487F314546019124D43B8),
+    ( 512, 0x18D2D110DCC9BCA1617E49599013CB6B, 0x06EEB0D56508040F545F610E9F5A78EC, 0x918C0F2C7656AB6D7564693DD526E28D),
+    (2048, 0xF736557FD47073A5DD59E2C3A5F038E0, 0x7FB03F7E7186C3EA230D43F30206260B, 0xE862D841C07049AFD32E975821D6519F),
+    (2240, 0xCCB134FBFA7CE49D6E73A90539CF2948, 0x50A1FE17B338995FED385111126FBA6F, 0x1E89EE710A768055B26C938C7AF3A71F),
+    (2367, 0xE89C0F6FF369B427CB37AEB9E5D361ED, 0xD23AAE4B76C31ECB6F5360AE69C2F406, 0x343654A35ACF0DAE293FA8E5173BB5E7),
+];
+
+/// Input of the CityHash tests, vector `i` hashes the `i` bytes at offset `i * i`
+fn city_test_buffer() -> Vec<u8> {
+    const K0: u64 = 0xc3a5c85c97cb3127;
+    let (mut a, mut b) = (9u64, 777u64);
+
+    (0..300 * 300)
+        .map(|i| {
+            a = a.wrapping_add(b);
+            b = b.wrapping_add(a);
+            a = (a ^ (a >> 41)).wrapping_mul(K0);
+            b = (b ^ (b >> 41)).wrapping_mul(K0).wrapping_add(i);
+            (b >> 37) as u8
+        })
+        .collect()
+}
+
+// (vector index, CityHash-128 low and high 64 bits), packed into a u128 with the low bits on top like cityhash-rs does
+#[rustfmt::skip]
+const CITYHASH_128: &[(usize, u64, u64)] = &[
+    (   0, 0x3DF09DFC64C09A2B, 0x3CB540C392E51E29),
+    (   1, 0xC3CDC41E1DF33513, 0x2C138FF2596D42F6),
+    (   4, 0x4D09E42F09CC3495, 0x666236631B9F253B),
+    (  12, 0xD93251758985EE6C, 0x32A9E9F82BA2A932),
+    (  16, 0xAC059617F5906673, 0x94D50D3DCD3069A7),
+    (  20, 0x7EBC034235BC122F, 0xD9A7783D4EDD8049),
+    (  40, 0x3DF9B04434771542, 0xFEDDCE785CCB661F),
+    (  70, 0xBC61414F9802ECAF, 0x8EDD1E7A50562924),
+    ( 128, 0xADC52DDDB76F6E5E, 0x4AAD4E925A962B68),
+    ( 200, 0x84064A6DCF916340, 0xFBF55A26790E0EBB),
+    ( 298, 0x967E970DF9673D2A, 0xD465247CFFA415C0),
+];
+
+// (input length, wyhash) with seed 0 over the counting input of the wyhash crate tests, which match the C implementation
+#[rustfmt::skip]
+const WYHASH: &[(usize, u64)] = &[
+    (   0, 0xF961F936E29C9345),
+    (   1, 0x8C73A8AB46596AE4),
+    (   3, 0x66523B8400601D1D),
+    (   4, 0xBBDB0DD7F6771760),
+    (   8, 0x812DEC5A9EC0C24F),
+    (  16, 0xCBC7992BDC99FB53),
+    (  17, 0xB1708065869961D2),
+    (  32, 0x8C881003AEBDCB80),
+    (  33, 0x8C9F56447486C4A5),
+    (  48, 0xA9625E945756D4CF),
+    (  49, 0xBE6961401287703C),
+    (  64, 0x360AADBD2E6B95DD),
+    ( 100, 0xB5BAAB885535CC23),
+    ( 255, 0xBF883493756EF2B6),
+];
+
+// (hash, input, expected) with seed 0 or the default seed
+#[rustfmt::skip]
+const HASHES_32: &[(&str, &[u8], u32)] = &[
+    ("FarmHash-32", b"", 0xDC56D17A),
+    ("FarmHash-32", b"abc", 0x2F635EC7),
+    ("FarmHash-32", b"hello world", 0x19A7581A),
+    ("FarmHash-32", b"size:  a.out:  bad magic", 0xC6246B8D),
+    ("FarmHash-32", b"Discard medicine more than two years old.", 0xE273108F),
+    ("MurmurHash3-32", b"", 0),
+    ("MurmurHash3-32", b"Hello, world!", 0xC0363E43),
+    ("MurmurHash3-32", b"123456789012345678901", 0x7B4F3DA6),
+    ("MurmurHash3-32", b"Lorem ipsum dolor sit amet, consectetur adipisicing elit", 0x3BF7E870),
+];
+
+#[rustfmt::skip]
+const HASHES_64: &[(&str, &[u8], u64)] = &[
+    ("FarmHash-64", b"", 0x9AE16A3B2F90404F),
+    ("FarmHash-64", b"abc", 0x24A5B3A074E7F369),
+    ("FarmHash-64", b"size:  a.out:  bad magic", 0x80D73B843BA57DB8),
+    ("FarmHash-64", b"Discard medicine more than two years old.", 0x2D072041B535155D),
+    ("FarmHash-64", b"For every action there is an equal and opposite government program.", 0x8452FBB0C8F98C4F),
+    ("rapidhash-v1", b"", 0x5A6EF77074EBC84B),
+    ("rapidhash-v1", b"\n", 0x457B89AE59B62CAF),
+    ("rapidhash-v1", b"something\n", 0xDD91317EEFDB7BF5),
+    ("rapidhash-v1", b"abcdefghijklmnopqrstuvwxyz01234567890123456789\n", 0x93B886C7E91B0D82),
+    ("komihash", b"This is a 32-byte testing string", 0x05AD960802903A9D),
+    ("komihash", b"The cat is out of the bag", 0xD15723521D3C37B1),
+    ("komihash", b"A 16-byte string", 0x467CAA28EA3DA7A6),
+    ("komihash", b"The new string", 0xF18E67BC90C43233),
+    ("komihash", b"7 chars", 0x2C514F6E5DCB11CB),
+    ("FNV-1a-64", b"", 0xCBF29CE484222325),
+    ("FNV-1a-64", b"a", 0xAF63DC4C8601EC8C),
+    ("FNV-1a-64", b"foobar", 0x85944171F73967E8),
+];
+
+// (checksum, input, expected), "123456789" is the check input of the CRC catalogue
+#[rustfmt::skip]
+const CHECKSUMS_32: &[(&str, &[u8], u32)] = &[
+    ("CRC-32", b"", 0),
+    ("CRC-32", b"123456789", 0xCBF43926),
+    ("CRC-32C", b"", 0),
+    ("CRC-32C", b"123456789", 0xE3069283),
+    ("Adler-32", b"", 1),
+    ("Adler-32", b"Wikipedia", 0x11E60398),
+    ("Adler-32", b"123456789", 0x091E01DE),
+];
+
+#[rustfmt::skip]
+const CHECKSUMS_64: &[(&str, &[u8], u64)] = &[
+    ("CRC-64/ECMA-182", b"", 0),
+    ("CRC-64/ECMA-182", b"123456789", 0x6C40DF5F0B497347),
+    ("CRC-64/XZ", b"", 0),
+    ("CRC-64/XZ", b"123456789", 0x995DC9BBDF1939FA),
+    ("CRC-64/NVME", b"", 0),
+    ("CRC-64/NVME", b"123456789", 0xAE8B14860A799888),
+];
+
+// (hash, input, expected digest in hex) from the specifications or their reference implementations
+#[rustfmt::skip]
+const DIGESTS: &[(&str, &[u8], &str)] = &[
+    ("SHA-224", b"abc", "23097d223405d8228642a477bda255b32aadbce4bda0b3f7e36c9da7"),
+    ("SHA-512-224", b"abc", "4634270f707b6a54daae7530460842e20e37ed265ceee9a43e8924aa"),
+    ("RIPEMD-160", b"", "9c1185a5c5e9fc54612808977ee8f548b2258d31"),
+    ("RIPEMD-160", b"abc", "8eb208f7e05d987a9b044a8e98c6b087f15a0bfc"),
+    ("SM3", b"abc", "66c7f0f462eeedd9d1f2d46bdc10e4e24167c4875cf2f7a2297da02b8f4ba8e0"),
+    ("Whirlpool", b"", "19fa61d75522a4669b44e39c1d2e1726c530232130d407f89afee0964997f7a73e83be698b288febcf88e3e03c4f0757ea8964e59b63d93708b138cc42a66eb3"),
+    ("Streebog-256", b"", "3f539a213e97c802cc229d474c6aa32a825a360b2a933a949fd925208d9ce1bb"),
+    ("Streebog-512", b"", "8e945da209aa869f0455928529bcae4679e9873ab707b55315f56ceb98bef0a7362f715528356ee83cda5f2aac4c6ad2ba3a715c1bcd81cb8e9f90bf4c1c1a8a"),
+    ("Groestl-256", b"", "1a52d11d550039be16107f9c58db9ebcc417f16f736adb2502567119f0083467"),
+    ("Groestl-512", b"", "6d3ad29d279110eef3adbd66de2a0345a77baede1557f5d099fce0c03d6dc2ba8e6d4a6633dfbd66053c20faa87d1a11f39a7fbe4a6c2f009801370308fc4ad8"),
+    ("Skein-256", b"", "c8877087da56e072870daa843f176e9453115929094c3a40c463a196c29bf7ba"),
+    ("Skein-512", b"", "bc5b4c50925519c290cc634277ae3d6257212395cba733bbad37a4af0fa06af41fca7903d06564fea7a2d3730dbdb80c1f85562dfcc070334ea4d1d9e72cba7a"),
+    ("Tiger", b"", "3293ac630c13f0245f92bbb1766e16167a4e58492dde73f3"),
+    ("MetroHash-64", b"012345678901234567890123456789012345678901234567890123456789012", "6b753dae06704bad"),
+    ("MetroHash-128", b"012345678901234567890123456789012345678901234567890123456789012", "c77ce2bfa4ed9f9b0548b2ac5074a297"),
+    ("MurmurHash3-x86-128", b"", "00000000000000000000000000000000"),
+    ("MurmurHash3-x86-128", b"Hello, world!", "a7dbac26fc8d63f063422b40c3d4fd0a"),
+    ("MurmurHash3-x86-128", b"123456789012345678901", "e3502dfb0f036f39a38311ea5eb6859a"),
+    ("MurmurHash3-x64-128", b"", "00000000000000000000000000000000"),
+    ("MurmurHash3-x64-128", b"Hello, world!", "df65d6d2d12d51f164c5f3a85066322c"),
+    ("MurmurHash3-x64-128", b"123456789012345678901", "5568ee163cf2d7a2d33df5a50237e6fe"),
+];
+
+/// `(hash, input, expected output)`, with the output in the byte order the hashes are registered with
+fn test_vectors() -> Vec<(&'static str, Vec<u8>, Vec<u8>)> {
+    let buf = sanity_buffer();
+    let mut vectors = vec![];
+
+    for &(len, xxh32, xxh64) in XXH {
+        let input = buf[..len].to_vec();
+        vectors.push(("XXH-32", input.clone(), u32_to_vec(xxh32)));
+        vectors.push(("XXH-64", input, u64_to_vec(xxh64)));
+    }
+
+    for &(len, default, seeded, secret) in XXH3_64 {
+        let input = buf[..len].to_vec();
+        vectors.push(("XXH3-64", input.clone(), u64_to_vec(default)));
+        vectors.push(("XXH3-64-seeded", input.clone(), u64_to_vec(seeded)));
+        vectors.push(("XXH3-64-secret", input, u64_to_vec(secret)));
+    }
+
+    for &(len, default, seeded, secret) in XXH3_128 {
+        let input = buf[..len].to_vec();
+        vectors.push(("XXH3-128", input.clone(), u128_to_vec(default)));
+        vectors.push(("XXH3-128-seeded", input.clone(), u128_to_vec(seeded)));
+        vectors.push(("XXH3-128-secret", input, u128_to_vec(secret)));
+    }
+
+    let city_buf = city_test_buffer();
+    for &(i, low, high) in CITYHASH_128 {
+        let input = city_buf[i * i..i * i + i].to_vec();
+        let hash = (low as u128) << 64 | high as u128;
+        vectors.push(("CityHash-128", input, u128_to_vec(hash)));
+    }
+
+    let counting = (0..=255).collect::<Vec<u8>>();
+    for &(len, hash) in WYHASH {
+        vectors.push(("wyhash", counting[..len].to_vec(), u64_to_vec(hash)));
+    }
+
+    for &(name, input, hash) in HASHES_32 {
+        vectors.push((name, input.to_vec(), u32_to_vec(hash)));
+    }
+
+    for &(name, input, hash) in HASHES_64 {
+        vectors.push((name, input.to_vec(), u64_to_vec(hash)));
+    }
+
+    for &(name, input, checksum) in CHECKSUMS_32 {
+        vectors.push((name, input.to_vec(), u32_to_vec(checksum)));
+    }
+
+    for &(name, input, checksum) in CHECKSUMS_64 {
+        vectors.push((name, input.to_vec(), u64_to_vec(checksum)));
+    }
+
+    for &(name, input, digest) in DIGESTS {
+        vectors.push((name, input.to_vec(), hex::decode(digest).unwrap()));
+    }
+
+    vectors
+}
+
+/// Check the hashes against the test vectors of their algorithm, hashes without vectors are skipped
+pub fn verify(hashes: &[HashEntry]) {
+    let vectors = test_vectors();
+
+    for entry in hashes {
+        for (_, input, expected) in vectors.iter().filter(|(name, ..)| *name == entry.name) {
+            assert_eq!(
+                hex::encode((entry.func)(input)),
+                hex::encode(expected),
+                "{} {} doesn't match test vector with {} byte input",
+                entry.implementation,
+                entry.name,
+                input.len()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_vectors() {
+        super::verify(&crate::registry::registry(crate::hashes()));
+    }
+}
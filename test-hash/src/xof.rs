@@ -0,0 +1,302 @@
+//! Extendable-output functions, with absorb and squeeze measured separately
+
+use std::time::Instant;
+
+use crate::encoding::{self, Encoding};
+use crate::report::{Metadata, Report};
+use crate::Format;
+
+/// Squeezes the next bytes of output from an XOF that has absorbed all of its input
+type Squeezer = Box<dyn FnMut(&mut [u8])>;
+
+/// Absorbs the input parts in order, TupleHash takes each part as one tuple element
+type XofFn = Box<dyn Fn(&[&[u8]]) -> Squeezer>;
+
+/// Block size used by ParallelHash when benchmarking
+const PARALLEL_HASH_BLOCK_SIZE: usize = 8192;
+
+/// Block size of the NIST SP 800-185 ParallelHash samples
+const SAMPLE_BLOCK_SIZE: usize = 8;
+
+fn keccak_xof<H>(mut hasher: H, parts: &[&[u8]]) -> Squeezer
+where
+    H: tiny_keccak::Hasher + tiny_keccak::IntoXof,
+    H::Xof: 'static,
+{
+    use tiny_keccak::Xof;
+
+    for part in parts {
+        hasher.update(part);
+    }
+    let mut xof = hasher.into_xof();
+    Box::new(move |output| xof.squeeze(output))
+}
+
+fn keccak_squeeze<H>(mut hasher: H, parts: &[&[u8]]) -> Squeezer
+where
+    H: tiny_keccak::Hasher + tiny_keccak::Xof + 'static,
+{
+    for part in parts {
+        hasher.update(part);
+    }
+    Box::new(move |output| hasher.squeeze(output))
+}
+
+#[rustfmt::skip]
+fn xofs(custom: &[u8], block_size: usize) -> Vec<(&'static str, &'static str, XofFn)> {
+    // every entry gets its own copy of the customization string
+    let c = || custom.to_vec();
+
+    vec![
+        // sha3
+        ( "sha3", "SHAKE128", Box::new(|b| {
+            use sha3::digest::{ExtendableOutput, Update, XofReader};
+            let mut hasher = sha3::Shake128::default();
+            b.iter().for_each(|part| hasher.update(part));
+            let mut reader = hasher.finalize_xof();
+            Box::new(move |output| reader.read(output))
+        }) ),
+        ( "sha3", "SHAKE256", Box::new(|b| {
+            use sha3::digest::{ExtendableOutput, Update, XofReader};
+            let mut hasher = sha3::Shake256::default();
+            b.iter().for_each(|part| hasher.update(part));
+            let mut reader = hasher.finalize_xof();
+            Box::new(move |output| reader.read(output))
+        }) ),
+
+        // tiny-keccak
+        ( "tiny-keccak", "SHAKE128", Box::new(|b| keccak_squeeze(tiny_keccak::Shake::v128(), b)) ),
+        ( "tiny-keccak", "SHAKE256", Box::new(|b| keccak_squeeze(tiny_keccak::Shake::v256(), b)) ),
+        // the function name N is left empty, it is reserved for functions defined by NIST
+        {
+            let custom = c();
+            ( "tiny-keccak", "cSHAKE128", Box::new(move |b| keccak_squeeze(tiny_keccak::CShake::v128(&[], &custom), b)) )
+        },
+        {
+            let custom = c();
+            ( "tiny-keccak", "cSHAKE256", Box::new(move |b| keccak_squeeze(tiny_keccak::CShake::v256(&[], &custom), b)) )
+        },
+        {
+            let custom = c();
+            ( "tiny-keccak", "KangarooTwelve", Box::new(move |b| keccak_xof(tiny_keccak::KangarooTwelve::new(custom.clone()), b)) )
+        },
+        {
+            let custom = c();
+            ( "tiny-keccak", "ParallelHash128", Box::new(move |b| keccak_xof(tiny_keccak::ParallelHash::v128(&custom, block_size), b)) )
+        },
+        {
+            let custom = c();
+            ( "tiny-keccak", "ParallelHash256", Box::new(move |b| keccak_xof(tiny_keccak::ParallelHash::v256(&custom, block_size), b)) )
+        },
+        {
+            let custom = c();
+            ( "tiny-keccak", "TupleHash128", Box::new(move |b| keccak_xof(tiny_keccak::TupleHash::v128(&custom), b)) )
+        },
+        {
+            let custom = c();
+            ( "tiny-keccak", "TupleHash256", Box::new(move |b| keccak_xof(tiny_keccak::TupleHash::v256(&custom), b)) )
+        },
+
+        // blake3
+        ( "blake3", "BLAKE3", Box::new(|b| {
+            let mut hasher = blake3::Hasher::new();
+            b.iter().for_each(|part| {
+                hasher.update(part);
+            });
+            let mut reader = hasher.finalize_xof();
+            Box::new(move |output| reader.fill(output))
+        }) ),
+    ]
+}
+
+fn sequence(len: u8) -> Vec<u8> {
+    (0..len).collect()
+}
+
+/// Algorithm, customization, message parts and the expected hex encoded start of the output
+#[rustfmt::skip]
+fn test_vectors() -> Vec<(&'static str, &'static str, Vec<Vec<u8>>, &'static str)> {
+    // input of the ParallelHash samples, three 8 byte blocks
+    let blocks = [0x00, 0x10, 0x20].iter().flat_map(|&b| b..b + 8).collect::<Vec<u8>>();
+    // tuple elements of the TupleHash samples
+    let tuple = |len: usize| vec![sequence(3), (0x10..0x16).collect(), (0x20..0x29).collect()][..len].to_vec();
+
+    vec![
+        // FIPS 202, empty input
+        ( "SHAKE128", "", vec![vec![]],
+          "7f9c2ba4e88f827d616045507605853ed73b8093f6efbc88eb1a6eacfa66ef263cb1eea988004b93103cfb0aeefd2a686e01fa4a58e8a3639ca8a1e3f9ae57e2" ),
+        ( "SHAKE256", "", vec![vec![]],
+          "46b9dd2b0ba88d13233b3feb743eeb243fcd52ea62b81b82b50c27646ed5762fd75dc4ddd8c0f200cb05019d67b592f6fc821c49479ab48640292eacb3b7c4be" ),
+
+        // NIST SP 800-185 cSHAKE samples #1 and #3
+        ( "cSHAKE128", "Email Signature", vec![sequence(4)],
+          "c1c36925b6409a04f1b504fcbca9d82b4017277cb5ed2b2065fc1d3814d5aaf5" ),
+        ( "cSHAKE256", "Email Signature", vec![sequence(4)],
+          "d008828e2b80ac9d2218ffee1d070c48b8e4c87bff32c9699d5b6896eee0edd164020e2be0560858d9c00c037e34a96937c561a74c412bb4c746469527281c8c" ),
+
+        // KangarooTwelve draft, empty message and customization
+        ( "KangarooTwelve", "", vec![vec![]],
+          "1ac2d450fc3b4205d19da7bfca1b37513c0803577ac7167f06fe2ce1f0ef39e5" ),
+
+        // NIST SP 800-185 ParallelHashXOF samples #1 to #6, with 8 byte blocks
+        ( "ParallelHash128", "", vec![blocks.clone()],
+          "fe47d661e49ffe5b7d999922c062356750caf552985b8e8ce6667f2727c3c8d3" ),
+        ( "ParallelHash128", "Parallel Data", vec![blocks.clone()],
+          "ea2a793140820f7a128b8eb70a9439f93257c6e6e79b4a540d291d6dae7098d7" ),
+        ( "ParallelHash256", "", vec![blocks.clone()],
+          "c10a052722614684144d28474850b410757e3cba87651ba167a5cbddff7f466675fbf84bcae7378ac444be681d729499afca667fb879348bfdda427863c82f1c" ),
+        ( "ParallelHash256", "Parallel Data", vec![blocks],
+          "538e105f1a22f44ed2f5cc1674fbd40be803d9c99bf5f8d90a2c8193f3fe6ea768e5c1a20987e2c9c65febed03887a51d35624ed12377594b5585541dc377efc" ),
+
+        // NIST SP 800-185 TupleHashXOF samples #1 to #6
+        ( "TupleHash128", "", tuple(2),
+          "2f103cd7c32320353495c68de1a8129245c6325f6f2a3d608d92179c96e68488" ),
+        ( "TupleHash128", "My Tuple App", tuple(2),
+          "3fc8ad69453128292859a18b6c67d7ad85f01b32815e22ce839c49ec374e9b9a" ),
+        ( "TupleHash128", "My Tuple App", tuple(3),
+          "900fe16cad098d28e74d632ed852f99daab7f7df4d99e775657885b4bf76d6f8" ),
+        ( "TupleHash256", "", tuple(2),
+          "03ded4610ed6450a1e3f8bc44951d14fbc384ab0efe57b000df6b6df5aae7cd568e77377daf13f37ec75cf5fc598b6841d51dd207c991cd45d210ba60ac52eb9" ),
+        ( "TupleHash256", "My Tuple App", tuple(2),
+          "6483cb3c9952eb20e830af4785851fc597ee3bf93bb7602c0ef6a65d741aeca7e63c3b128981aa05c6d27438c79d2754bb1b7191f125d6620fca12ce658b2442" ),
+        ( "TupleHash256", "My Tuple App", tuple(3),
+          "0c59b11464f2336c34663ed51b2b950bec743610856f36c28d1d088d8a2446284dd09830a6a178dc752376199fae935d86cfdee5913d4922dfd369b66a53c897" ),
+
+        // BLAKE3 reference test vectors, empty input, extended output
+        ( "BLAKE3", "", vec![vec![]],
+          "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262e00f03e7b69af26b7faaf09fcd333050338ddfe085b8cc869ca98b206c08243a" ),
+    ]
+}
+
+/// Check every XOF implementation against the test vectors for its algorithm
+pub fn verify() {
+    let vectors = test_vectors();
+
+    for (impl_name, xof_name, _) in xofs(&[], SAMPLE_BLOCK_SIZE) {
+        let mut verified = false;
+
+        for (_, custom, parts, expected) in vectors.iter().filter(|(n, ..)| *n == xof_name) {
+            let (_, _, xof_func) = xofs(custom.as_bytes(), SAMPLE_BLOCK_SIZE)
+                .into_iter()
+                .find(|(i, x, _)| *i == impl_name && *x == xof_name)
+                .unwrap();
+
+            let parts = parts.iter().map(|p| p.as_slice()).collect::<Vec<_>>();
+            let mut output = vec![0u8; expected.len() / 2];
+            xof_func(&parts)(&mut output);
+            assert_eq!(
+                &hex::encode(output),
+                expected,
+                "{} {} doesn't match test vector",
+                impl_name,
+                xof_name
+            );
+            verified = true;
+        }
+
+        assert!(verified, "{} {} has no test vector", impl_name, xof_name);
+    }
+}
+
+#[derive(serde::Serialize)]
+struct XofResult<'a> {
+    implementation: &'a str,
+    xof: &'a str,
+    output_len: usize,
+    absorb_mb_per_s: f64,
+    squeeze_mb_per_s: f64,
+    output_prefix: String,
+}
+
+pub struct Options<'a> {
+    pub format: Format,
+    pub filter: Option<&'a str>,
+    pub show_hashes: bool,
+    pub encoding: Encoding,
+    pub size: usize,
+    pub output_len: usize,
+    pub customization: &'a str,
+}
+
+pub fn run(options: &Options) {
+    let mut xofs = xofs(options.customization.as_bytes(), PARALLEL_HASH_BLOCK_SIZE);
+    xofs.sort_by(|(_, xof1, _), (_, xof2, _)| {
+        xof1.to_ascii_lowercase().cmp(&xof2.to_ascii_lowercase())
+    });
+
+    let bytes = vec![0u8; options.size * 1024 * 1024];
+    let mut output = vec![0u8; options.output_len];
+
+    let metadata = Metadata::new("xof", 1, Some(bytes.len()), "zeros");
+    let mut report = Report::new(options.format, metadata);
+
+    if options.format == Format::Csv {
+        println!("implementation,xof,output bytes,absorb MB/s,squeeze MB/s");
+    }
+
+    for (impl_name, xof_name, xof_func) in &xofs {
+        if let Some(filter) = options.filter {
+            if !impl_name.contains(filter) {
+                continue; // skip
+            }
+        }
+
+        let start_time = Instant::now();
+        let mut squeeze = xof_func(&[&bytes]);
+        let absorb_duration = start_time.elapsed().as_secs_f64();
+
+        let start_time = Instant::now();
+        squeeze(&mut output);
+        let squeeze_duration = start_time.elapsed().as_secs_f64();
+
+        let absorb_speed = (bytes.len() as f64) / (1024f64 * 1024f64) / absorb_duration;
+        let squeeze_speed = (output.len() as f64) / (1024f64 * 1024f64) / squeeze_duration;
+
+        match options.format {
+            Format::Text => {
+                print!(
+                    "{:15} {:13} absorb {:>6.0} MB/s squeeze {:>6.0} MB/s",
+                    xof_name, impl_name, absorb_speed, squeeze_speed
+                );
+
+                if options.show_hashes {
+                    let shown = output.len().min(32);
+                    println!(
+                        "  {}",
+                        encoding::encode(options.encoding, xof_name, &output[..shown])
+                    );
+                } else {
+                    println!();
+                }
+            }
+            Format::Csv => {
+                println!(
+                    "{},{},{},{:.0},{:.0}",
+                    impl_name,
+                    xof_name,
+                    output.len(),
+                    absorb_speed,
+                    squeeze_speed
+                );
+            }
+            Format::Json | Format::Markdown => report.add(XofResult {
+                implementation: impl_name,
+                xof: xof_name,
+                output_len: output.len(),
+                absorb_mb_per_s: absorb_speed,
+                squeeze_mb_per_s: squeeze_speed,
+                output_prefix: hex::encode(&output[..output.len().min(32)]),
+            }),
+        }
+    }
+
+    report.finish();
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_vectors() {
+        super::verify();
+    }
+}
@@ -0,0 +1,117 @@
+//! Compares native and WASM speeds from the CSV output of the tools
+
+use bench_common::results::Record;
+
+/// Speeds of one build of the tool
+pub struct Build {
+    pub name: &'static str,
+    pub records: Vec<Record>,
+}
+
+/// Ratio of the speeds of two builds, shown as a column
+pub struct Ratio {
+    pub name: &'static str,
+    /// Index of the build whose speed is divided
+    pub numerator: usize,
+    /// Index of the build whose speed it's divided by
+    pub denominator: usize,
+}
+
+/// Prints the speeds of the builds side by side followed by the ratios, in the order of the first
+/// build, and the geometric mean of each ratio. Speeds that rounded down to zero have no ratios
+pub fn print(builds: &[Build], ratios: &[Ratio]) {
+    let all_records = || builds.iter().flat_map(|b| &b.records);
+    let key_width = all_records()
+        .map(|r| r.key.join(" ").len())
+        .max()
+        .unwrap_or(0);
+    let metric_width = all_records().map(|r| r.metric.len()).max().unwrap_or(0);
+    let width = |name: &str| name.len().max(14);
+
+    print!(
+        "{:key_width$} {:metric_width$}",
+        "",
+        "",
+        key_width = key_width,
+        metric_width = metric_width
+    );
+    for name in builds
+        .iter()
+        .map(|b| b.name)
+        .chain(ratios.iter().map(|r| r.name))
+    {
+        print!(" {:>width$}", name, width = width(name));
+    }
+    println!();
+
+    let find = |records: &[Record], record: &Record| {
+        records
+            .iter()
+            .find(|r| r.key == record.key && r.metric == record.metric)
+            .map(|r| r.mb_per_s)
+    };
+
+    // hashes and codecs that don't exist in every build, such as those using C or x86
+    // intrinsics, are shown without ratios
+    let mut records = Vec::<&Record>::new();
+    for record in all_records() {
+        if !records
+            .iter()
+            .any(|r| r.key == record.key && r.metric == record.metric)
+        {
+            records.push(record);
+        }
+    }
+
+    let mut values = vec![vec![]; ratios.len()];
+    let mut zero_speeds = 0;
+    for record in records {
+        let speeds = builds
+            .iter()
+            .map(|b| find(&b.records, record))
+            .collect::<Vec<_>>();
+        if speeds.iter().any(|&s| s.is_some_and(|s| s <= 0.0)) {
+            zero_speeds += 1;
+        }
+
+        print!(
+            "{:key_width$} {:metric_width$}",
+            record.key.join(" "),
+            record.metric,
+            key_width = key_width,
+            metric_width = metric_width
+        );
+        for (build, speed) in builds.iter().zip(&speeds) {
+            let speed = match speed {
+                Some(mb_per_s) => format!("{:.2} MB/s", mb_per_s),
+                None => "-".to_string(),
+            };
+            print!(" {:>width$}", speed, width = width(build.name));
+        }
+        for (ratio, values) in ratios.iter().zip(&mut values) {
+            let value = match (speeds[ratio.numerator], speeds[ratio.denominator]) {
+                (Some(numerator), Some(denominator)) if numerator > 0.0 && denominator > 0.0 => {
+                    values.push(numerator / denominator);
+                    format!("{:.2}x", numerator / denominator)
+                }
+                _ => "-".to_string(),
+            };
+            print!(" {:>width$}", value, width = width(ratio.name));
+        }
+        println!();
+    }
+
+    println!();
+    for (ratio, values) in ratios.iter().zip(&values) {
+        if !values.is_empty() {
+            let mean = (values.iter().map(|v| v.ln()).sum::<f64>() / values.len() as f64).exp();
+            println!("geometric mean {}: {:.2}x", ratio.name, mean);
+        }
+    }
+    if zero_speeds > 0 {
+        println!(
+            "{} results with a zero speed left out of the geometric means, use a larger --size",
+            zero_speeds
+        );
+    }
+}
@@ -0,0 +1,151 @@
+//! Runs the native and `wasm32-wasi` builds of test-hash or test-compress with the same arguments,
+//! the WASM builds in an embedded Wasmtime, and compares their speeds
+
+use anyhow::{anyhow, Context};
+use bench_common::results;
+use std::{path::PathBuf, str::FromStr};
+use structopt::StructOpt;
+use variant::Variant;
+
+mod compare;
+mod variant;
+mod wasm;
+
+#[derive(Copy, Clone)]
+enum Tool {
+    TestHash,
+    TestCompress,
+}
+
+impl FromStr for Tool {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "test-hash" => Ok(Tool::TestHash),
+            "test-compress" => Ok(Tool::TestCompress),
+            _ => Err(format!(
+                "unknown tool {}, expected test-hash or test-compress",
+                s
+            )),
+        }
+    }
+}
+
+impl Tool {
+    fn name(self) -> &'static str {
+        match self {
+            Tool::TestHash => "test-hash",
+            Tool::TestCompress => "test-compress",
+        }
+    }
+
+    /// Arguments for CSV output, given before the passed through ones so they apply to the
+    /// benchmark rather than to a subcommand
+    fn csv_args(self) -> &'static [&'static str] {
+        match self {
+            Tool::TestHash => &["--format", "csv"],
+            Tool::TestCompress => &["--csv"],
+        }
+    }
+}
+
+#[derive(StructOpt)]
+#[structopt(about = "Compares native and WASM speeds of test-hash or test-compress")]
+struct Options {
+    /// Tool to compare, test-hash or test-compress
+    tool: Tool,
+
+    /// Native build of the tool, defaults to the one next to this binary
+    #[structopt(long, parse(from_os_str))]
+    native: Option<PathBuf>,
+
+    /// wasm32-wasi build of the tool, defaults to the one in target/wasm32-wasi/release
+    #[structopt(long, parse(from_os_str))]
+    wasm: Option<PathBuf>,
+
+    /// Also compare a wasm32-wasi build with the simd128 target feature against the baseline one
+    #[structopt(long)]
+    simd128: bool,
+
+    /// wasm32-wasi build of the tool with simd128, defaults to the one in
+    /// target/simd128/wasm32-wasi/release
+    #[structopt(long, parse(from_os_str))]
+    wasm_simd128: Option<PathBuf>,
+
+    /// Build the compared variants of the tool with cargo before running them
+    #[structopt(long)]
+    build: bool,
+
+    /// Directory the WASM builds can access, as their current directory
+    #[structopt(long, parse(from_os_str), default_value = ".")]
+    dir: PathBuf,
+
+    /// Arguments passed to every build, after --
+    #[structopt(last = true)]
+    args: Vec<String>,
+}
+
+fn main() -> anyhow::Result<()> {
+    let options = Options::from_args();
+    let tool = options.tool;
+
+    // this binary is built to target/release, next to the native tools
+    let target_dir = std::env::current_exe()?
+        .parent()
+        .and_then(|dir| dir.parent())
+        .map(|dir| dir.to_path_buf())
+        .context("failed to find the target directory")?;
+
+    let mut variants = vec![
+        (Variant::Native, options.native),
+        (Variant::Wasm, options.wasm),
+    ];
+    if options.simd128 {
+        variants.push((Variant::WasmSimd128, options.wasm_simd128));
+    }
+
+    let mut args = tool
+        .csv_args()
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect::<Vec<_>>();
+    args.extend(options.args);
+
+    let mut builds = vec![];
+    for (variant, path) in variants {
+        if options.build {
+            variant.build(&target_dir, tool.name())?;
+        }
+        let path = path.unwrap_or_else(|| variant.path(&target_dir, tool.name()));
+        let output = variant.run(&path, &args, &options.dir)?;
+        let (_, records) = results::parse_csv(&output)
+            .map_err(|e| anyhow!("failed to parse the output of {}: {}", path.display(), e))?;
+        builds.push(compare::Build {
+            name: variant.name(),
+            records,
+        });
+    }
+
+    // slowdowns are relative to native, the speedup of simd128 relative to the baseline WASM
+    let mut ratios = vec![compare::Ratio {
+        name: "wasm slowdown",
+        numerator: 0,
+        denominator: 1,
+    }];
+    if options.simd128 {
+        ratios.push(compare::Ratio {
+            name: "simd128 slowdown",
+            numerator: 0,
+            denominator: 2,
+        });
+        ratios.push(compare::Ratio {
+            name: "simd128 speedup",
+            numerator: 2,
+            denominator: 1,
+        });
+    }
+    compare::print(&builds, &ratios);
+
+    Ok(())
+}
@@ -0,0 +1,107 @@
+//! The native and WebAssembly builds of a tool that are compared, and how cargo builds them
+
+use anyhow::{bail, Context};
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Variant {
+    Native,
+    Wasm,
+    /// WebAssembly with the 128-bit SIMD proposal enabled, which Wasmtime supports by default
+    WasmSimd128,
+}
+
+impl Variant {
+    pub fn name(self) -> &'static str {
+        match self {
+            Variant::Native => "native",
+            Variant::Wasm => "wasm",
+            Variant::WasmSimd128 => "wasm simd128",
+        }
+    }
+
+    /// Target directory cargo builds the variant in, simd128 builds get their own so they don't
+    /// replace the baseline ones
+    fn target_dir(self, target_dir: &Path) -> PathBuf {
+        match self {
+            Variant::Native | Variant::Wasm => target_dir.to_path_buf(),
+            Variant::WasmSimd128 => target_dir.join("simd128"),
+        }
+    }
+
+    /// Where cargo puts the tool built for the variant
+    pub fn path(self, target_dir: &Path, tool: &str) -> PathBuf {
+        let target_dir = self.target_dir(target_dir);
+        match self {
+            Variant::Native => {
+                target_dir
+                    .join("release")
+                    .join(format!("{}{}", tool, std::env::consts::EXE_SUFFIX))
+            }
+            Variant::Wasm | Variant::WasmSimd128 => target_dir
+                .join("wasm32-wasi")
+                .join("release")
+                .join(format!("{}.wasm", tool)),
+        }
+    }
+
+    /// Builds the tool for the variant with cargo, in the workspace of the target directory
+    pub fn build(self, target_dir: &Path, tool: &str) -> anyhow::Result<()> {
+        let workspace = target_dir
+            .parent()
+            .context("the target directory isn't in a workspace")?;
+        let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+
+        let mut command = Command::new(cargo);
+        command
+            .current_dir(workspace)
+            .args(["build", "--release", "-p", tool])
+            .arg("--target-dir")
+            .arg(self.target_dir(target_dir))
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit());
+        if self != Variant::Native {
+            command.args(["--target", "wasm32-wasi"]);
+        }
+        if self == Variant::WasmSimd128 {
+            command.env(
+                "CARGO_TARGET_WASM32_WASI_RUSTFLAGS",
+                "-C target-feature=+simd128",
+            );
+        }
+
+        eprintln!("building {} {}", self.name(), tool);
+        let status = command.status().context("failed to run cargo")?;
+        if !status.success() {
+            bail!(
+                "cargo failed to build {} {} with {}",
+                self.name(),
+                tool,
+                status
+            );
+        }
+        Ok(())
+    }
+
+    /// Runs the build at the path with the arguments, in `dir`, and returns its standard output
+    pub fn run(self, path: &Path, args: &[String], dir: &Path) -> anyhow::Result<String> {
+        eprintln!("running {}", path.display());
+        if self != Variant::Native {
+            return crate::wasm::run(path, args, dir);
+        }
+
+        let output = Command::new(path)
+            .args(args)
+            .current_dir(dir)
+            .stderr(Stdio::inherit())
+            .output()
+            .with_context(|| format!("failed to run {}", path.display()))?;
+        if !output.status.success() {
+            bail!("{} failed with {}", path.display(), output.status);
+        }
+        Ok(String::from_utf8(output.stdout)?)
+    }
+}
@@ -0,0 +1,52 @@
+//! Runs a `wasm32-wasi` command module in an embedded Wasmtime, capturing its standard output
+
+use anyhow::{bail, Context};
+use std::path::Path;
+use wasi_common::pipe::WritePipe;
+use wasmtime::{Engine, Linker, Module, Store};
+use wasmtime_wasi::sync::{ambient_authority, Dir, WasiCtxBuilder};
+
+/// Runs the module with the arguments, giving it access to `dir` as its current directory, and
+/// returns what it wrote to standard output. Standard error is passed through.
+pub fn run(path: &Path, args: &[String], dir: &Path) -> anyhow::Result<String> {
+    let engine = Engine::default();
+    let mut linker = Linker::new(&engine);
+    wasmtime_wasi::add_to_linker(&mut linker, |ctx| ctx)?;
+
+    let preopen = Dir::open_ambient_dir(dir, ambient_authority())
+        .with_context(|| format!("failed to open {}", dir.display()))?;
+    let stdout = WritePipe::new_in_memory();
+    let ctx = WasiCtxBuilder::new()
+        // the module path is the first argument, as it is for a native process
+        .arg(&path.display().to_string())?
+        .args(args)?
+        .stdout(Box::new(stdout.clone()))
+        .inherit_stderr()
+        .preopened_dir(preopen, ".")?
+        .build();
+    let mut store = Store::new(&engine, ctx);
+
+    let module = Module::from_file(&engine, path)
+        .with_context(|| format!("failed to load {}", path.display()))?;
+    linker.module(&mut store, "", &module)?;
+    let result = linker
+        .get_default(&mut store, "")?
+        .typed::<(), ()>(&store)?
+        .call(&mut store, ());
+
+    // a command exits through proc_exit with a non-zero code, which traps
+    if let Err(err) = result {
+        match err.downcast_ref::<wasi_common::I32Exit>() {
+            Some(exit) if exit.0 == 0 => {}
+            Some(exit) => bail!("{} exited with code {}", path.display(), exit.0),
+            None => return Err(err),
+        }
+    }
+
+    drop(store);
+    let stdout = stdout
+        .try_into_inner()
+        .expect("stdout is only shared with the dropped store")
+        .into_inner();
+    Ok(String::from_utf8(stdout)?)
+}